@@ -1,18 +1,32 @@
 use serde::Deserialize;
 use std::path::PathBuf;
 use url::Url;
+// internal
+use crate::cli::Secret;
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct Git {
     pub(crate) enable: bool,
     pub(crate) repository_url: Option<Url>,
     pub(crate) repository_name: Option<String>,
-    pub(crate) repository_token: Option<String>,
+    pub(crate) repository_token: Option<Secret>,
     pub(crate) repository_ssh_key: Option<PathBuf>,
+    pub(crate) remote: Option<String>,
+    pub(crate) retries: Option<u32>,
     pub(crate) disable_push: Option<bool>,
+    pub(crate) bookmark_files: Option<Vec<PathBuf>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Logging {
+    /// Logging level -> 0: ERROR, 1: WARN, 2: INFO, 3: DEBUG, 4: TRACE
+    pub(crate) level: Option<u8>,
 }
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct FloccusCliConfig {
+    /// Local data folder (where git repositories are cloned)
+    pub(crate) data_dir: Option<PathBuf>,
+    pub(crate) logging: Option<Logging>,
     pub(crate) git: Git,
 }
@@ -1,7 +1,13 @@
 mod cli_args;
 mod config;
+mod secret;
 
+#[cfg(feature = "check-links")]
+pub use cli_args::CheckLinksArgs;
 pub use cli_args::{
-    parse_cli_and_override, AddArgs, Cli, Commands, FindArgs, InitArgs, Placement, PrintArgs,
-    RemoveArgs, Under,
+    parse_cli_and_override, print_completions, AddArgs, Cli, Commands, ConfigArgs, ConfigCommands,
+    CopyArgs, Date, DiffArgs, ExportArgs, ExportFormat, FindArgs, ImportArgs, ImportFormat,
+    InitArgs, LogArgs, MergeArgs, MoveArgs, OpenArgs, Placement, PrintArgs, PrintFormat, PruneArgs,
+    RemoveArgs, RewriteUrlsArgs, Under, UndoArgs, UniqueScope,
 };
+pub use secret::{redact_url, Secret, REDACTED};
@@ -1,9 +1,15 @@
 // std
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 // third-party
 use git2::build::{CheckoutBuilder, RepoBuilder};
-use git2::{Cred, FetchOptions, Progress, RemoteCallbacks, Repository};
+use git2::{
+    Commit, Cred, CredentialType, ErrorClass, ErrorCode, FetchOptions, Oid, Progress, PushOptions,
+    Remote, RemoteCallbacks, Repository, ResetType, RevertOptions, Sort, Status, StatusOptions,
+    Time,
+};
+use thiserror::Error;
 use tracing::{debug, info, warn};
 use url::Url;
 
@@ -15,11 +21,155 @@ struct State {
     // newline: bool,
 }
 
+/// Credentials used to authenticate against a remote repository
+pub struct GitAuth<'a> {
+    /// Ssh private key path
+    pub ssh_key: Option<&'a Path>,
+    /// Ssh private key passphrase (if the key is encrypted)
+    pub ssh_passphrase: Option<&'a str>,
+    /// Try to authenticate with ssh-agent before using `ssh_key`
+    pub ssh_agent: bool,
+    /// Repository token (https remote)
+    pub token: Option<&'a str>,
+}
+
+/// Remote repository used for git fetch / git push
+pub struct GitRemote<'a> {
+    /// Remote name (e.g. origin)
+    pub name: &'a str,
+    /// Number of retries on transient (e.g. network) errors
+    pub retries: u32,
+    /// On push rejection (non fast-forward), pull (git fetch + git merge) then push again
+    pub auto_merge: bool,
+}
+
+/// Delay before the first retry (doubled for each retry)
+const GIT_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Return true if the error is an authentication error
+///
+/// Note: libssh2 authentication failures (e.g. bad key file or passphrase) are reported as
+///       generic ssh errors
+fn is_auth_error(e: &git2::Error) -> bool {
+    e.code() == ErrorCode::Auth
+        || (e.class() == ErrorClass::Ssh && e.message().contains("authenticat"))
+}
+
+/// Return true if the error is (probably) transient (e.g. a network error) and worth a retry
+fn is_transient_error(e: &git2::Error) -> bool {
+    if is_auth_error(e) {
+        return false;
+    }
+    match e.code() {
+        ErrorCode::Certificate | ErrorCode::NotFastForward => false,
+        _ => matches!(
+            e.class(),
+            ErrorClass::Net | ErrorClass::Http | ErrorClass::Ssh | ErrorClass::Os
+        ),
+    }
+}
+
+/// Call `f` and retry (with exponential backoff) on transient errors
+fn with_retry<T>(
+    operation: &str,
+    retries: u32,
+    mut f: impl FnMut() -> Result<T, git2::Error>,
+) -> Result<T, git2::Error> {
+    let mut backoff = GIT_RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(e) if attempt < retries && is_transient_error(&e) => {
+                attempt += 1;
+                warn!(
+                    "{} failed: {} - retrying in {}s ({}/{})",
+                    operation,
+                    e.message(),
+                    backoff.as_secs(),
+                    attempt,
+                    retries
+                );
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+            res => return res,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GitCloneError {
+    #[error("Ssh authentication failed (ssh key: {ssh_key}) - please check the ssh key path, its passphrase (--ssh-passphrase or FLOCCUS_SSH_PASSPHRASE) or your ssh-agent: {source}")]
+    SshAuth {
+        ssh_key: String,
+        source: git2::Error,
+    },
+    #[error("Https authentication failed - please check the repository token (--token or FLOCCUS_GIT_TOKEN): {0}")]
+    TokenAuth(git2::Error),
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+}
+
+/// Install a credentials callback
+///
+/// For ssh remotes, try (in order): ssh-agent (if enabled) then the ssh key file.
+/// For https remotes, use the repository token (if any) as username with an empty password.
+fn set_credentials<'a>(cb: &mut RemoteCallbacks<'a>, auth: &'a GitAuth<'a>) {
+    // Note: libgit2 calls the credentials callback again each time an authentication fails,
+    //       so keep track of the attempts in order to try every method once
+    let attempts = Cell::new(0);
+    cb.credentials(move |_url, username_from_url, allowed_types| {
+        let attempt = attempts.get();
+        attempts.set(attempt + 1);
+
+        if allowed_types.contains(CredentialType::SSH_KEY) {
+            let username = username_from_url.unwrap_or("git");
+            let attempt = if auth.ssh_agent { attempt } else { attempt + 1 };
+
+            match (attempt, auth.ssh_key) {
+                (0, _) => {
+                    debug!("Trying ssh authentication using ssh-agent");
+                    Cred::ssh_key_from_agent(username)
+                }
+                (1, Some(ssh_key)) => {
+                    debug!("Trying ssh authentication using key: {}", ssh_key.display());
+                    Cred::ssh_key(username, None, ssh_key, auth.ssh_passphrase)
+                }
+                _ => Err(git2::Error::new(
+                    ErrorCode::Auth,
+                    ErrorClass::Ssh,
+                    "no more ssh credentials to try",
+                )),
+            }
+        } else if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) && attempt == 0 {
+            // Note: a token embedded in the url (as username) is still supported
+            match auth.token.or(username_from_url) {
+                Some(token) => {
+                    debug!("Trying https authentication using token");
+                    Cred::userpass_plaintext(token, "")
+                }
+                None => Err(git2::Error::new(
+                    ErrorCode::Auth,
+                    ErrorClass::Http,
+                    "no repository token provided",
+                )),
+            }
+        } else {
+            Err(git2::Error::new(
+                ErrorCode::Auth,
+                ErrorClass::None,
+                "no more credentials to try",
+            ))
+        }
+    });
+}
+
 pub fn git_clone(
     url: &Url,
     to_path: &Path,
-    ssh_key: Option<&Path>,
-) -> Result<Repository, git2::Error> {
+    remote_name: &str,
+    auth: &GitAuth,
+) -> Result<Repository, GitCloneError> {
     let state = RefCell::new(State {
         progress: None,
         total: 0,
@@ -29,18 +179,7 @@ pub fn git_clone(
     });
     let mut cb = RemoteCallbacks::new();
 
-    if url.scheme() == "ssh" {
-        if let Some(ssh_key) = ssh_key {
-            cb.credentials(|_url, username_from_url, _allowed_types| {
-                Cred::ssh_key(
-                    username_from_url.unwrap(), // Safe to unwrap - as url is of Url type
-                    None,
-                    ssh_key,
-                    None,
-                )
-            });
-        }
-    }
+    set_credentials(&mut cb, auth);
 
     cb.transfer_progress(|stats| {
         let mut state = state.borrow_mut();
@@ -62,9 +201,26 @@ pub fn git_clone(
     let mut fetch_opts = FetchOptions::new();
     fetch_opts.remote_callbacks(cb);
     let repo = RepoBuilder::new()
+        .remote_create(|repo, _name, url| repo.remote(remote_name, url))
         .fetch_options(fetch_opts)
         .with_checkout(co)
-        .clone(url.to_string().as_str(), to_path)?;
+        .clone(url.to_string().as_str(), to_path)
+        .map_err(|e| {
+            let is_auth_error = is_auth_error(&e);
+            if is_auth_error && url.scheme() != "ssh" {
+                GitCloneError::TokenAuth(e)
+            } else if is_auth_error {
+                GitCloneError::SshAuth {
+                    ssh_key: auth
+                        .ssh_key
+                        .map(|k| k.display().to_string())
+                        .unwrap_or_default(),
+                    source: e,
+                }
+            } else {
+                GitCloneError::Git(e)
+            }
+        })?;
 
     Ok(repo)
 }
@@ -73,6 +229,8 @@ pub fn git_fetch<'a>(
     repo: &'a git2::Repository,
     refs: &[&str],
     remote: &'a mut git2::Remote,
+    retries: u32,
+    auth: &GitAuth,
 ) -> Result<git2::AnnotatedCommit<'a>, git2::Error> {
     /*
     let mut cb = git2::RemoteCallbacks::new();
@@ -99,14 +257,19 @@ pub fn git_fetch<'a>(
     });
     */
 
-    let mut fetch_opts = git2::FetchOptions::new();
-    // fetch_opts.remote_callbacks(cb);
-
-    // Always fetch all tags.
-    // Perform a download and also update tips
-    fetch_opts.download_tags(git2::AutotagOption::All);
     debug!("Fetching {} for repo", remote.name().unwrap());
-    remote.fetch(refs, Some(&mut fetch_opts), None)?;
+    with_retry("git fetch", retries, || {
+        let mut cb = RemoteCallbacks::new();
+        set_credentials(&mut cb, auth);
+
+        let mut fetch_opts = git2::FetchOptions::new();
+        fetch_opts.remote_callbacks(cb);
+
+        // Always fetch all tags.
+        // Perform a download and also update tips
+        fetch_opts.download_tags(git2::AutotagOption::All);
+        remote.fetch(refs, Some(&mut fetch_opts), None)
+    })?;
 
     // If there are local objects (we got a thin pack), then tell the user
     // how many objects we saved from having to cross the network.
@@ -137,6 +300,7 @@ fn fast_forward(
     repo: &Repository,
     lb: &mut git2::Reference,
     rc: &git2::AnnotatedCommit,
+    force: bool,
 ) -> Result<(), git2::Error> {
     let name = match lb.name() {
         Some(s) => s.to_string(),
@@ -144,15 +308,17 @@ fn fast_forward(
     };
     let msg = format!("Fast-Forward: Setting {} to id: {}", name, rc.id());
     info!("{}", msg);
+    // Update the working directory before moving the branch: the current HEAD is then used as
+    // the checkout baseline and local modifications are reported as conflicts (unless forced)
+    let mut co = CheckoutBuilder::new();
+    if force {
+        co.force();
+    } else {
+        co.safe();
+    }
+    repo.checkout_tree(&repo.find_object(rc.id(), None)?, Some(&mut co))?;
     lb.set_target(rc.id(), &msg)?;
     repo.set_head(&name)?;
-    repo.checkout_head(Some(
-        git2::build::CheckoutBuilder::default()
-            // For some reason the force is required to make the working directory actually get updated
-            // I suspect we should be adding some logic to handle dirty working directory states
-            // but this is just an example so maybe not.
-            .force(),
-    ))?;
     Ok(())
 }
 
@@ -160,6 +326,7 @@ fn normal_merge(
     repo: &Repository,
     local: &git2::AnnotatedCommit,
     remote: &git2::AnnotatedCommit,
+    force: bool,
 ) -> Result<(), git2::Error> {
     let local_tree = repo.find_commit(local.id())?.tree()?;
     let remote_tree = repo.find_commit(remote.id())?.tree()?;
@@ -169,11 +336,41 @@ fn normal_merge(
     let mut idx = repo.merge_trees(&ancestor, &local_tree, &remote_tree, None)?;
 
     if idx.has_conflicts() {
-        warn!("Merge conflicts detected...");
-        repo.checkout_index(Some(&mut idx), None)?;
-        return Ok(());
+        let conflicted = idx
+            .conflicts()?
+            .filter_map(|c| {
+                let c = c.ok()?;
+                let entry = c.our.or(c.their).or(c.ancestor)?;
+                Some(String::from_utf8_lossy(&entry.path).into_owned())
+            })
+            .collect::<Vec<_>>();
+        // Write the conflict markers in the working tree (so the user can resolve them)
+        repo.checkout_index(
+            Some(&mut idx),
+            Some(
+                CheckoutBuilder::new()
+                    .allow_conflicts(true)
+                    .conflict_style_merge(true),
+            ),
+        )?;
+        return Err(git2::Error::new(
+            ErrorCode::Conflict,
+            ErrorClass::Merge,
+            format!(
+                "Merge conflicts in: {} (in {}) - please resolve them (git status) then commit",
+                conflicted.join(", "),
+                repo.workdir().unwrap_or(repo.path()).display()
+            ),
+        ));
     }
     let result_tree = repo.find_tree(idx.write_tree_to(repo)?)?;
+    // Set working tree to match the merge result
+    // Note: done before moving HEAD so the checkout baseline is the local commit
+    let mut co = CheckoutBuilder::new();
+    if force {
+        co.force();
+    }
+    repo.checkout_tree(result_tree.as_object(), Some(&mut co))?;
     // now create the merge commit
     let msg = format!("Merge: {} into {}", remote.id(), local.id());
     let sig = repo.signature()?;
@@ -188,8 +385,6 @@ fn normal_merge(
         &result_tree,
         &[&local_commit, &remote_commit],
     )?;
-    // Set working tree to match head.
-    repo.checkout_head(None)?;
     Ok(())
 }
 
@@ -197,6 +392,7 @@ pub fn git_merge<'a>(
     repo: &'a Repository,
     remote_branch: &str,
     fetch_commit: git2::AnnotatedCommit<'a>,
+    force: bool,
 ) -> Result<(), git2::Error> {
     // 1. do a merge analysis
     let analysis = repo.merge_analysis(&[&fetch_commit])?;
@@ -208,7 +404,7 @@ pub fn git_merge<'a>(
         let refname = format!("refs/heads/{}", remote_branch);
         match repo.find_reference(&refname) {
             Ok(mut r) => {
-                fast_forward(repo, &mut r, &fetch_commit)?;
+                fast_forward(repo, &mut r, &fetch_commit, force)?;
             }
             Err(_) => {
                 // The branch doesn't exist so just set the reference to the
@@ -232,14 +428,223 @@ pub fn git_merge<'a>(
     } else if analysis.0.is_normal() {
         // do a normal merge
         let head_commit = repo.reference_to_annotated_commit(&repo.head()?)?;
-        normal_merge(repo, &head_commit, &fetch_commit)?;
+        normal_merge(repo, &head_commit, &fetch_commit, force)?;
     } else {
         info!("Nothing to do...");
     }
     Ok(())
 }
 
-pub fn git_push(repo: &Repository, file_to_add: &Path) -> Result<(), git2::Error> {
+/// Return true if the given file has local (uncommitted) changes
+pub fn git_is_dirty(repo: &Repository, file: &Path) -> Result<bool, git2::Error> {
+    let mut status_opts = StatusOptions::new();
+    status_opts
+        .pathspec(file)
+        .include_untracked(false)
+        .include_ignored(false);
+    let statuses = repo.statuses(Some(&mut status_opts))?;
+    Ok(statuses
+        .iter()
+        .any(|entry| entry.status() != Status::CURRENT))
+}
+
+/// Return true if the repository HEAD is unborn (i.e. an empty repository without any commit)
+pub fn git_is_empty(repo: &Repository) -> Result<bool, git2::Error> {
+    match repo.head() {
+        Ok(_) => Ok(false),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => {
+            Ok(true)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// A commit as listed by `git_log`
+pub struct GitCommit {
+    /// Abbreviated commit id
+    pub short_id: String,
+    /// First line of the commit message
+    pub summary: String,
+    /// Author name
+    pub author: String,
+    /// Author date
+    pub time: Time,
+}
+
+impl GitCommit {
+    /// Author date (in the author time zone) as YYYY-MM-DD HH:MM:SS +HHMM
+    pub fn date(&self) -> String {
+        let offset = i64::from(self.time.offset_minutes());
+        let seconds = self.time.seconds() + offset * 60;
+        let (days, seconds_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+        // Civil date from the number of days since 1970-01-01
+        // (see http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}{:02}{:02}",
+            year,
+            month,
+            day,
+            seconds_of_day / 3600,
+            seconds_of_day % 3600 / 60,
+            seconds_of_day % 60,
+            self.time.sign(),
+            offset.abs() / 60,
+            offset.abs() % 60
+        )
+    }
+}
+
+/// List the last commits (most recent first) reachable from HEAD
+///
+/// If `file` is provided, only list the commits modifying this file (compared to the first parent).
+/// An empty repository has no commits.
+pub fn git_log(
+    repo: &Repository,
+    limit: usize,
+    file: Option<&Path>,
+) -> Result<Vec<GitCommit>, git2::Error> {
+    if git_is_empty(repo)? {
+        return Ok(vec![]);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    revwalk.push_head()?;
+
+    // File content (blob id) in a commit tree (None if the file does not exist)
+    let file_id = |commit: &Commit, file: &Path| -> Result<Option<Oid>, git2::Error> {
+        match commit.tree()?.get_path(file) {
+            Ok(entry) => Ok(Some(entry.id())),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+
+    let mut commits = vec![];
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if let Some(file) = file {
+            let parent_file_id = match commit.parents().next() {
+                Some(parent) => file_id(&parent, file)?,
+                None => None,
+            };
+            if file_id(&commit, file)? == parent_file_id {
+                continue;
+            }
+        }
+
+        let author = commit.author();
+        commits.push(GitCommit {
+            short_id: commit
+                .as_object()
+                .short_id()?
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author: author.name().unwrap_or_default().to_string(),
+            time: author.when(),
+        });
+    }
+    Ok(commits)
+}
+
+#[derive(Error, Debug)]
+pub enum GitShowError {
+    #[error("Unknown git revision: {0}")]
+    UnknownRevision(String),
+    #[error("File {1} does not exist at git revision {0}")]
+    FileNotFound(String, String),
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+}
+
+/// Read a file content at a given revision (e.g. HEAD~3, a commit id or a tag), like
+/// `git show <rev>:<file>`
+pub fn git_show_file(repo: &Repository, rev: &str, file: &Path) -> Result<Vec<u8>, GitShowError> {
+    let object = repo.revparse_single(rev).map_err(|e| match e.code() {
+        ErrorCode::NotFound | ErrorCode::InvalidSpec | ErrorCode::Ambiguous => {
+            GitShowError::UnknownRevision(rev.to_string())
+        }
+        _ => GitShowError::Git(e),
+    })?;
+    let tree = object.peel_to_commit()?.tree()?;
+    let entry = tree.get_path(file).map_err(|e| match e.code() {
+        ErrorCode::NotFound => {
+            GitShowError::FileNotFound(rev.to_string(), file.display().to_string())
+        }
+        _ => GitShowError::Git(e),
+    })?;
+    let blob = repo.find_blob(entry.id())?;
+    Ok(blob.content().to_vec())
+}
+
+/// Find a remote given its name (the error lists the available remotes if not found)
+pub fn git_find_remote<'a>(repo: &'a Repository, name: &str) -> Result<Remote<'a>, git2::Error> {
+    repo.find_remote(name).map_err(|e| {
+        if e.code() == ErrorCode::NotFound {
+            let remotes = repo
+                .remotes()
+                .map(|remotes| remotes.iter().flatten().collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            git2::Error::from_str(
+                format!(
+                    "Cannot find git remote: {} (available remotes: {})",
+                    name, remotes
+                )
+                .as_str(),
+            )
+        } else {
+            e
+        }
+    })
+}
+
+/// Message of the commits created by `git_push`
+pub const GIT_COMMIT_MESSAGE: &str = "Floccus bookmarks update";
+
+/// Commit message: `GIT_COMMIT_MESSAGE` or the given message (then `GIT_COMMIT_MESSAGE` is kept
+/// as the last line so the commit is still recognized as a bookmark update, see `git_revert_head`)
+fn commit_message(message: Option<&str>) -> String {
+    match message {
+        Some(message) => format!("{}\n\n{}", message.trim_end(), GIT_COMMIT_MESSAGE),
+        None => GIT_COMMIT_MESSAGE.to_string(),
+    }
+}
+
+/// A commit created by `git_push` (see `commit_message`)
+fn is_bookmark_update(commit: &Commit) -> bool {
+    commit
+        .message()
+        .and_then(|message| message.trim_end().lines().last())
+        .is_some_and(|last_line| last_line == GIT_COMMIT_MESSAGE)
+}
+
+/// Commit the given file (with an optional commit message, see `commit_message`) then push
+pub fn git_push(
+    repo: &Repository,
+    file_to_add: &Path,
+    message: Option<&str>,
+    remote: &GitRemote,
+    auth: &GitAuth,
+) -> Result<(), git2::Error> {
+    // Check remote first (before any commit)
+    let mut remote_ = git_find_remote(repo, remote.name)?;
+
     // Configured author signature
     let author = repo.signature()?;
 
@@ -260,24 +665,519 @@ pub fn git_push(repo: &Repository, file_to_add: &Path) -> Result<(), git2::Error
     let new_tree = repo.find_tree(new_tree_oid)?;
 
     // for simple commit, use current head as parent
-    // TODO: test
     // you need more than one parent if the commit is a merge
-    let head = repo.head()?;
-    // FIXME: unwrap
-    let parent = repo.find_commit(head.target().unwrap())?;
+    // an empty repository (no commit yet) has no HEAD - create an initial commit (no parent)
+    let parent = if git_is_empty(repo)? {
+        debug!("Empty repository, creating initial commit on branch main");
+        repo.set_head("refs/heads/main")?;
+        None
+    } else {
+        Some(repo.head()?.peel_to_commit()?)
+    };
+    let parents = parent.iter().collect::<Vec<_>>();
 
     let _commit_oid = repo.commit(
         Some("HEAD"),
         &author,
         &author,
-        "Floccus bookmarks update",
+        commit_message(message).as_str(),
         &new_tree,
-        &[&parent],
+        &parents,
     )?;
 
-    // git push
-    let mut origin = repo.find_remote("origin")?;
-    origin.push(&["refs/heads/main:refs/heads/main"], None)?;
+    push_main(repo, &mut remote_, remote, auth)
+}
 
-    Ok(())
+/// git push the current branch (HEAD) to the remote (without creating any commit)
+pub fn git_push_head(
+    repo: &Repository,
+    remote: &GitRemote,
+    auth: &GitAuth,
+) -> Result<(), git2::Error> {
+    let mut remote_ = git_find_remote(repo, remote.name)?;
+    push_main(repo, &mut remote_, remote, auth)
+}
+
+/// git push branch main (with a git pull then push again on rejection if auto merge is enabled)
+fn push_main(
+    repo: &Repository,
+    remote_: &mut Remote,
+    remote: &GitRemote,
+    auth: &GitAuth,
+) -> Result<(), git2::Error> {
+    let remote_branch = "main";
+    match push_branch(remote_, remote_branch, remote.retries, auth) {
+        Err(e) if e.code() == ErrorCode::NotFastForward && remote.auto_merge => {
+            // Someone else pushed in the meantime - git pull then push again (only once)
+            warn!(
+                "Push rejected ({}) - pulling changes from {} then pushing again",
+                e.message(),
+                remote.name
+            );
+            let fetch_commit = git_fetch(repo, &[remote_branch], remote_, remote.retries, auth)?;
+            git_merge(repo, remote_branch, fetch_commit, false).map_err(|e| {
+                if e.code() == ErrorCode::Conflict {
+                    git2::Error::new(e.code(), e.class(), format!("Cannot push: {}", e.message()))
+                } else {
+                    e
+                }
+            })?;
+            push_branch(remote_, remote_branch, remote.retries, auth)
+        }
+        res => res,
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GitRevertError {
+    #[error("Nothing to revert: the repository has no commit")]
+    NoCommit,
+    #[error("Last commit ({0}) is not a bookmark update (commit message: {1:?}) - use --force to revert it anyway")]
+    NotABookmarkUpdate(String, String),
+    #[error("Cannot revert the last commit, conflicts in: {0}")]
+    Conflicts(String),
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+}
+
+/// Revert the last commit (HEAD) - as git revert: a new commit undoing the changes is created
+///
+/// Only a commit created by `git_push` (commit message ending with `GIT_COMMIT_MESSAGE`) is
+/// reverted unless `force` is true (a merge commit is reverted against its first parent).
+/// The revert commit message can be given (default: Revert "<summary>").
+/// Returns the revert commit id.
+pub fn git_revert_head(
+    repo: &Repository,
+    force: bool,
+    message: Option<&str>,
+) -> Result<Oid, GitRevertError> {
+    if git_is_empty(repo)? {
+        return Err(GitRevertError::NoCommit);
+    }
+    let head = repo.head()?.peel_to_commit()?;
+    let head_id = head.as_object().short_id()?;
+    let head_id = head_id.as_str().unwrap_or_default();
+    let summary = head.summary().unwrap_or_default();
+    if !force && !is_bookmark_update(&head) {
+        return Err(GitRevertError::NotABookmarkUpdate(
+            head_id.to_string(),
+            summary.to_string(),
+        ));
+    }
+
+    // Changes are applied to the index & the working directory
+    let mut revert_opts = RevertOptions::new();
+    if head.parent_count() > 1 {
+        revert_opts.mainline(1);
+    }
+    repo.revert(&head, Some(&mut revert_opts))?;
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        let conflicts = index
+            .conflicts()?
+            .filter_map(|c| c.ok())
+            .filter_map(|c| c.our.or(c.their).or(c.ancestor))
+            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            .collect::<Vec<_>>();
+        // Leave the repository as before the revert
+        repo.cleanup_state()?;
+        repo.reset(head.as_object(), ResetType::Hard, None)?;
+        return Err(GitRevertError::Conflicts(conflicts.join(", ")));
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let author = repo.signature()?;
+    let message = format!(
+        "{}\n\nThis reverts commit {}.",
+        message
+            .map(|m| m.trim_end().to_string())
+            .unwrap_or_else(|| format!("Revert \"{}\"", summary)),
+        head.id()
+    );
+    let oid = repo.commit(Some("HEAD"), &author, &author, &message, &tree, &[&head])?;
+    repo.cleanup_state()?;
+    Ok(oid)
+}
+
+/// git push a branch (with retries on transient errors)
+///
+/// A reference rejected by the remote is returned as an error
+/// (with code `ErrorCode::NotFastForward` for a non fast-forward rejection)
+fn push_branch(
+    remote: &mut Remote,
+    branch: &str,
+    retries: u32,
+    auth: &GitAuth,
+) -> Result<(), git2::Error> {
+    let refspec = format!("refs/heads/{branch}:refs/heads/{branch}");
+    with_retry("git push", retries, || {
+        let rejected = RefCell::new(None);
+        let mut cb = RemoteCallbacks::new();
+        set_credentials(&mut cb, auth);
+        cb.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(format!("{} rejected: {}", refname, status));
+            }
+            Ok(())
+        });
+        let mut push_opts = PushOptions::new();
+        push_opts.remote_callbacks(cb);
+
+        remote.push(&[refspec.as_str()], Some(&mut push_opts))?;
+        drop(push_opts);
+
+        match rejected.into_inner() {
+            Some(msg) if msg.contains("non-fast-forward") || msg.contains("fetch first") => Err(
+                git2::Error::new(ErrorCode::NotFastForward, ErrorClass::Reference, msg),
+            ),
+            Some(msg) => Err(git2::Error::from_str(msg.as_str())),
+            None => Ok(()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init_repo(path: &Path) -> Repository {
+        let repo = Repository::init(path).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Floccus cli").unwrap();
+        config
+            .set_str("user.email", "floccus-cli@example.com")
+            .unwrap();
+        repo
+    }
+
+    /// Commit a file (with a fixed author date)
+    fn commit_file(repo: &Repository, file: &str, content: &str, message: &str, seconds: i64) {
+        std::fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = git2::Signature::new(
+            "Floccus cli",
+            "floccus-cli@example.com",
+            &Time::new(seconds, 60),
+        )
+        .unwrap();
+        let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+        let parents = parent.iter().collect::<Vec<_>>();
+        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_git_log() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(tmp_dir.path());
+        assert!(git_log(&repo, 10, None).unwrap().is_empty());
+
+        commit_file(&repo, "bookmarks.xbel", "1", "Add bookmarks", 1_700_000_000);
+        commit_file(&repo, "Readme.md", "readme", "Add readme", 1_700_000_100);
+        commit_file(
+            &repo,
+            "bookmarks.xbel",
+            "2",
+            "Update bookmarks\n\nDetails",
+            1_700_000_200,
+        );
+
+        let summaries =
+            |commits: Vec<GitCommit>| commits.into_iter().map(|c| c.summary).collect::<Vec<_>>();
+        let commits = git_log(&repo, 10, None).unwrap();
+        assert_eq!(commits[0].author, "Floccus cli");
+        assert_eq!(commits[0].date(), "2023-11-14 23:16:40 +0100");
+        assert_eq!(
+            summaries(commits),
+            vec!["Update bookmarks", "Add readme", "Add bookmarks"]
+        );
+        assert_eq!(
+            summaries(git_log(&repo, 2, None).unwrap()),
+            vec!["Update bookmarks", "Add readme"]
+        );
+        assert_eq!(
+            summaries(git_log(&repo, 10, Some(Path::new("bookmarks.xbel"))).unwrap()),
+            vec!["Update bookmarks", "Add bookmarks"]
+        );
+    }
+
+    #[test]
+    fn test_git_show_file() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(tmp_dir.path());
+        let file = Path::new("bookmarks.xbel");
+        commit_file(&repo, "bookmarks.xbel", "1", "Add bookmarks", 1_700_000_000);
+        commit_file(&repo, "Readme.md", "readme", "Add readme", 1_700_000_100);
+        commit_file(&repo, "bookmarks.xbel", "2", "Update", 1_700_000_200);
+
+        assert_eq!(git_show_file(&repo, "HEAD", file).unwrap(), b"2");
+        assert_eq!(git_show_file(&repo, "HEAD~1", file).unwrap(), b"1");
+        assert_eq!(git_show_file(&repo, "HEAD~2", file).unwrap(), b"1");
+        assert!(matches!(
+            git_show_file(&repo, "HEAD~2", Path::new("Readme.md")),
+            Err(GitShowError::FileNotFound(..))
+        ));
+        assert!(matches!(
+            git_show_file(&repo, "HEAD~5", file),
+            Err(GitShowError::UnknownRevision(..))
+        ));
+    }
+
+    #[test]
+    fn test_git_commit_message() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(tmp_dir.path());
+        let file = tmp_dir.path().join("bookmarks.xbel");
+        commit_file(
+            &repo,
+            "bookmarks.xbel",
+            "1",
+            "Initial commit",
+            1_700_000_000,
+        );
+        commit_file(
+            &repo,
+            "bookmarks.xbel",
+            commit_message(Some("Migrated from Chrome\n")).as_str(),
+            commit_message(Some("Migrated from Chrome\n")).as_str(),
+            1_700_000_100,
+        );
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Migrated from Chrome"));
+        assert!(is_bookmark_update(&head));
+
+        // Still recognized as a bookmark update
+        git_revert_head(&repo, false, Some("Undo migration")).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "1");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.summary(), Some("Undo migration"));
+        assert!(!is_bookmark_update(&head));
+    }
+
+    #[test]
+    fn test_git_revert_head() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo = init_repo(tmp_dir.path());
+        assert!(matches!(
+            git_revert_head(&repo, false, None),
+            Err(GitRevertError::NoCommit)
+        ));
+
+        let file = tmp_dir.path().join("bookmarks.xbel");
+        commit_file(
+            &repo,
+            "bookmarks.xbel",
+            "1",
+            "Initial commit",
+            1_700_000_000,
+        );
+        commit_file(
+            &repo,
+            "bookmarks.xbel",
+            "2",
+            GIT_COMMIT_MESSAGE,
+            1_700_000_100,
+        );
+        git_revert_head(&repo, false, None).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "1");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            head.summary(),
+            Some(format!("Revert \"{}\"", GIT_COMMIT_MESSAGE).as_str())
+        );
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+
+        // Not a bookmark update (the revert commit): only reverted with force
+        assert!(matches!(
+            git_revert_head(&repo, false, None),
+            Err(GitRevertError::NotABookmarkUpdate(..))
+        ));
+        git_revert_head(&repo, true, None).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_push_auto_merge() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let remote_path = tmp_dir.path().join("remote.git");
+        Repository::init_bare(&remote_path)
+            .unwrap()
+            .set_head("refs/heads/main")
+            .unwrap();
+        let auth = GitAuth {
+            ssh_key: None,
+            ssh_passphrase: None,
+            ssh_agent: false,
+            token: None,
+        };
+        let remote = GitRemote {
+            name: "origin",
+            retries: 0,
+            auto_merge: true,
+        };
+        let file = Path::new("bookmarks.xbel");
+        let lines = (0..20).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+
+        // Device A: initial commit
+        let repo_a = init_repo(&tmp_dir.path().join("a"));
+        repo_a
+            .remote("origin", remote_path.to_str().unwrap())
+            .unwrap();
+        let workdir_a = repo_a.workdir().unwrap().to_path_buf();
+        std::fs::write(workdir_a.join(file), lines.join("\n")).unwrap();
+        git_push(&repo_a, file, None, &remote, &auth).unwrap();
+
+        // Device B: clone
+        let repo_b =
+            Repository::clone(remote_path.to_str().unwrap(), tmp_dir.path().join("b")).unwrap();
+        let mut config = repo_b.config().unwrap();
+        config.set_str("user.name", "Floccus cli").unwrap();
+        config
+            .set_str("user.email", "floccus-cli@example.com")
+            .unwrap();
+        let workdir_b = repo_b.workdir().unwrap().to_path_buf();
+
+        // Device A: update first line & push
+        let mut lines_a = lines.clone();
+        lines_a[0] = "line 0 (from a)".to_string();
+        std::fs::write(workdir_a.join(file), lines_a.join("\n")).unwrap();
+        git_push(&repo_a, file, None, &remote, &auth).unwrap();
+
+        // Device B: update last line & push (rejected without auto merge)
+        let mut lines_b = lines.clone();
+        lines_b[19] = "line 19 (from b)".to_string();
+        std::fs::write(workdir_b.join(file), lines_b.join("\n")).unwrap();
+        let remote_no_merge = GitRemote {
+            auto_merge: false,
+            ..remote
+        };
+        let res = git_push(&repo_b, file, None, &remote_no_merge, &auth);
+        assert_eq!(res.unwrap_err().code(), ErrorCode::NotFastForward);
+        git_push(&repo_b, file, None, &remote, &auth).unwrap();
+
+        let content = std::fs::read_to_string(workdir_b.join(file)).unwrap();
+        assert!(content.contains("line 0 (from a)"));
+        assert!(content.contains("line 19 (from b)"));
+        let remote_repo = Repository::open_bare(&remote_path).unwrap();
+        let head = remote_repo
+            .find_reference("refs/heads/main")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(head.parent_count(), 2);
+
+        // Conflicting updates: reported as an error (no retry loop)
+        git_fetch(
+            &repo_a,
+            &["main"],
+            &mut repo_a.find_remote("origin").unwrap(),
+            0,
+            &auth,
+        )
+        .and_then(|c| git_merge(&repo_a, "main", c, false))
+        .unwrap();
+        let mut lines_a = std::fs::read_to_string(workdir_a.join(file))
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let mut lines_b = lines_a.clone();
+        lines_a[5] = "line 5 (from a)".to_string();
+        std::fs::write(workdir_a.join(file), lines_a.join("\n")).unwrap();
+        git_push(&repo_a, file, None, &remote, &auth).unwrap();
+        lines_b[5] = "line 5 (from b)".to_string();
+        std::fs::write(workdir_b.join(file), lines_b.join("\n")).unwrap();
+        let res = git_push(&repo_b, file, None, &remote, &auth);
+        let err = res.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Conflict);
+        assert!(err.message().contains("bookmarks.xbel"));
+        let content = std::fs::read_to_string(workdir_b.join(file)).unwrap();
+        assert!(content.contains("<<<<<<<"));
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        let net_error = git2::Error::new(ErrorCode::GenericError, ErrorClass::Net, "timeout");
+        assert!(is_transient_error(&net_error));
+        let auth_error = git2::Error::new(ErrorCode::Auth, ErrorClass::Http, "auth failed");
+        assert!(!is_transient_error(&auth_error));
+        let ssh_auth_error = git2::Error::new(
+            ErrorCode::GenericError,
+            ErrorClass::Ssh,
+            "Failed to authenticate SSH session: Unable to open private key file",
+        );
+        assert!(is_auth_error(&ssh_auth_error));
+        assert!(!is_transient_error(&ssh_auth_error));
+        let ssh_error = git2::Error::new(
+            ErrorCode::GenericError,
+            ErrorClass::Ssh,
+            "Failed getting banner",
+        );
+        assert!(!is_auth_error(&ssh_error));
+        assert!(is_transient_error(&ssh_error));
+        let nff_error = git2::Error::new(
+            ErrorCode::NotFastForward,
+            ErrorClass::Reference,
+            "cannot push non-fastforwardable reference",
+        );
+        assert!(!is_transient_error(&nff_error));
+    }
+
+    #[test]
+    fn test_with_retry() {
+        // Note: retries = 0 (no sleep)
+        let mut calls = 0;
+        let res: Result<(), _> = with_retry("test", 0, || {
+            calls += 1;
+            Err(git2::Error::new(
+                ErrorCode::GenericError,
+                ErrorClass::Net,
+                "timeout",
+            ))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 1);
+
+        // Non transient error: no retry
+        let mut calls = 0;
+        let res: Result<(), _> = with_retry("test", 3, || {
+            calls += 1;
+            Err(git2::Error::new(ErrorCode::Auth, ErrorClass::Ssh, "auth"))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 1);
+
+        // Ssh authentication failure (generic ssh error): no retry
+        let mut calls = 0;
+        let res: Result<(), _> = with_retry("test", 3, || {
+            calls += 1;
+            Err(git2::Error::new(
+                ErrorCode::GenericError,
+                ErrorClass::Ssh,
+                "Failed to authenticate SSH session: Callback returned error",
+            ))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 1);
+
+        // Transient error then success
+        let mut calls = 0;
+        let res = with_retry("test", 1, || {
+            calls += 1;
+            if calls == 1 {
+                Err(git2::Error::new(
+                    ErrorCode::GenericError,
+                    ErrorClass::Net,
+                    "timeout",
+                ))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(res.unwrap(), 2);
+    }
 }
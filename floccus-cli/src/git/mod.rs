@@ -1,3 +1,7 @@
 mod git_command;
 
-pub use git_command::{git_clone, git_fetch, git_merge, git_push};
+pub use git_command::{
+    git_clone, git_fetch, git_find_remote, git_is_dirty, git_is_empty, git_log, git_merge,
+    git_push, git_push_head, git_revert_head, git_show_file, GitAuth, GitRemote, GitRevertError,
+    GitShowError,
+};
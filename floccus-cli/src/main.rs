@@ -1,134 +1,215 @@
 mod cli;
 mod git;
+mod print;
+#[cfg(any(
+    feature = "fetch-title",
+    feature = "check-links",
+    feature = "fetch-xbel"
+))]
+mod web;
 // mod xbel;
 
 // std
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::error::Error;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 // third-party
 use directories::ProjectDirs;
-use git2::Repository;
+use git2::{ErrorCode, Repository};
+use globset::{GlobBuilder, GlobMatcher};
 use tempfile::{NamedTempFile, PathPersistError};
 use thiserror::Error;
 use toml_edit::{value, DocumentMut, TomlError};
-use tracing::level_filters::LevelFilter;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use url::Url;
 // internal
+#[cfg(feature = "check-links")]
+use crate::cli::CheckLinksArgs;
 use crate::cli::{
-    parse_cli_and_override, AddArgs, Cli, Commands, FindArgs, InitArgs, Placement, PrintArgs,
-    RemoveArgs, Under,
+    parse_cli_and_override, print_completions, redact_url, AddArgs, Cli, Commands, ConfigArgs,
+    ConfigCommands, CopyArgs, Date, DiffArgs, ExportArgs, ExportFormat, FindArgs, ImportArgs,
+    ImportFormat, InitArgs, LogArgs, MergeArgs, MoveArgs, OpenArgs, Placement, PrintArgs,
+    PrintFormat, PruneArgs, RemoveArgs, RewriteUrlsArgs, Secret, Under, UndoArgs, UniqueScope,
+    REDACTED,
+};
+use crate::git::{
+    git_clone, git_fetch, git_find_remote, git_is_dirty, git_is_empty, git_log, git_merge,
+    git_push, git_push_head, git_revert_head, git_show_file, GitAuth, GitRemote, GitRevertError,
+    GitShowError,
+};
+use crate::print::{paint, print_items, PrintOptions, BOOKMARK_STYLE, FOLDER_STYLE};
+use floccus_xbel::{
+    diff, WriteOptions, Xbel, XbelError, XbelInsertError, XbelItem, XbelItemOrEnd, XbelMoveError,
+    XbelNestingIterator, XbelPath,
 };
-use crate::git::{git_clone, git_fetch, git_merge, git_push};
-use floccus_xbel::{Xbel, XbelError, XbelItem, XbelItemOrEnd, XbelNestingIterator, XbelPath};
 
-const FLOCCUS_CLI_CONFIG_ENV: &str = "FLOCCUS_CLI_CONFIG";
 const FLOCCUS_CLI_QUALIFIER: &str = "app";
 const FLOCCUS_CLI_ORGANIZATION: &str = "";
 const FLOCCUS_CLI_APPLICATION: &str = "Floccus-cli";
+const FLOCCUS_CLI_FALLBACK_FOLDER: &str = "floccus-cli";
+
+const BOOKMARK_FILE_DEFAULT: &str = "bookmarks.xbel";
+/// Bookmark file path used when reading from stdin (--stdin)
+const STDIN_PATH: &str = "-";
 
 const FLOCCUS_CLI_CONFIG_SAMPLE: &str = r#"
+# Local data folder where git repositories are cloned (default: platform local data folder)
+# data_dir = "/srv/floccus-cli"
+
+[logging]
+    # Logging level -> 0: ERROR, 1: WARN, 2: INFO, 3: DEBUG, 4: TRACE
+    level = 2
+
 [git]
     enable = true
     repository_url = "https://github.com/__GITHUB_USER__/__GIT_REPO_NAME__.git"
     repository_name = "bookmarks"
     repository_token = ""
     repository_ssh_key = ""
+    remote = "origin"
+    # Number of retries on network errors (git fetch / git push)
+    retries = 2
     disable_push = true
+    # Bookmark files in the repository (the first one is used by default)
+    # bookmark_files = ["personal.xbel", "work.xbel"]
 "#;
 
+/// Floccus-cli platform folders (None if no home folder can be found, e.g. in a container)
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from(
+        FLOCCUS_CLI_QUALIFIER,
+        FLOCCUS_CLI_ORGANIZATION,
+        FLOCCUS_CLI_APPLICATION,
+    )
+}
+
+/// Folder used when the platform folders cannot be determined: ~/<home_relative>/floccus-cli
+/// (or the current directory if HOME is not set)
+fn fallback_dir(home_relative: &str) -> PathBuf {
+    match std::env::var_os("HOME") {
+        Some(home) if !home.is_empty() => PathBuf::from(home)
+            .join(home_relative)
+            .join(FLOCCUS_CLI_FALLBACK_FOLDER),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Cache folder (temp files)
+fn cache_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .unwrap_or_else(|| fallback_dir(".cache"))
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let default_config_path = project_dirs()
+        .map(|dirs| dirs.config_local_dir().to_path_buf())
+        .unwrap_or_else(|| fallback_dir(".config"))
+        .join("config.toml");
+
+    let cli = match parse_cli_and_override(&default_config_path) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Note: logging level (from command line or config) is only known after parsing the config file
+    //       RUST_LOG env variable (if set) takes precedence
     let filter = EnvFilter::builder()
-        .with_default_directive(LevelFilter::INFO.into())
+        .with_default_directive(cli.log_level_filter().into())
         .from_env_lossy();
     tracing_subscriber::registry()
-        .with(fmt::layer())
+        .with(fmt::layer().with_writer(std::io::stderr))
         .with(filter)
         .init();
 
-    let (config_path, config_path_expected): (Option<PathBuf>, PathBuf) = {
-        // if FLOCCUS_CLI_CONFIG environment variable is set use it, otherwise use local config dir.
-        let config_env = std::env::var(FLOCCUS_CLI_CONFIG_ENV);
-        if let Ok(config_env) = config_env {
-            (
-                Some(PathBuf::from(config_env.clone())),
-                PathBuf::from(config_env),
-            )
-        } else {
-            let cfg = ProjectDirs::from(
-                FLOCCUS_CLI_QUALIFIER,
-                FLOCCUS_CLI_ORGANIZATION,
-                FLOCCUS_CLI_APPLICATION,
-            )
-            .ok_or("Unable to determine local data directory")?
-            .config_local_dir()
-            .to_path_buf()
-            .join("config.toml");
-
-            if cfg.exists() {
-                (Some(cfg.clone()), cfg)
-            } else {
-                (None, cfg)
-            }
-        }
-    };
+    debug!("config file: {:?}", cli.config_file);
+    debug!("cli args: {:?}", cli.redacted());
 
-    debug!("config_path: {:?}", config_path);
-
-    let cli = parse_cli_and_override(config_path.clone())?;
-
-    debug!("cli args: {:?}", cli);
+    // Bookmark file for read only commands (--stdin or --from-url)
+    let read_only_file = read_only_bookmark_file(&cli);
 
     // if repo folder is provided - use it otherwise - use a local data dir
-    let repository_folder = if let Some(ref repository_folder) = cli.repository_folder {
+    let repository_folder = if read_only_file.is_some() {
+        // No git repository: bookmark file is STDIN_PATH or a url
+        PathBuf::new()
+    } else if let Some(ref xbel_file) = cli.xbel_file {
+        // No git repository: use the Xbel file folder
+        match xbel_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    } else if let Some(ref repository_folder) = cli.repository_folder {
         repository_folder.clone()
     } else {
         let repo_name = cli.repository_name.clone();
-        ProjectDirs::from(
-            FLOCCUS_CLI_QUALIFIER,
-            FLOCCUS_CLI_ORGANIZATION,
-            FLOCCUS_CLI_APPLICATION,
-        )
-        .ok_or("Unable to determine local data directory")?
-        .data_local_dir()
+        match cli.data_dir {
+            Some(ref data_dir) => data_dir.clone(),
+            None => project_dirs()
+                .map(|dirs| dirs.data_local_dir().to_path_buf())
+                .unwrap_or_else(|| fallback_dir(".local/share")),
+        }
         .join(repo_name)
     };
 
     info!("repository_folder: {}", repository_folder.display());
 
-    let cache_folder_ = ProjectDirs::from(
-        FLOCCUS_CLI_QUALIFIER,
-        FLOCCUS_CLI_ORGANIZATION,
-        FLOCCUS_CLI_APPLICATION,
-    )
-    .ok_or("Cannot determine cache folder")?;
-    let cache_folder = cache_folder_.cache_dir();
-
+    let cache_folder = cache_dir();
     if !cache_folder.exists() {
         debug!("Creating cache folder: {:?}", cache_folder);
-        std::fs::create_dir_all(cache_folder)?;
+        std::fs::create_dir_all(&cache_folder)?;
     }
 
     match &cli.command {
         Commands::Init(init_args) => {
-            let res = init_app(&cli, init_args, config_path_expected.as_path());
+            let config_path = cli.config_file.as_deref().unwrap_or(&default_config_path);
+            let res = init_app(&cli, init_args, config_path);
 
             if let Err(e) = res {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
+        Commands::Config(ConfigArgs { command }) => match command {
+            ConfigCommands::Path => config_path(&cli, &default_config_path),
+            ConfigCommands::Show => config_show(&cli, &repository_folder),
+        },
         Commands::Print(print_args) => {
-            let _repo = setup_repo(&cli, &repository_folder)?;
-            bookmark_print(print_args, repository_folder)?;
+            let mut repo = None;
+            let bookmark_file = if let Some(ref read_only_file) = read_only_file {
+                read_only_file.clone()
+            } else {
+                repo = setup_repo(&cli, &repository_folder)?;
+                select_bookmark_file(&cli, &repository_folder)?
+            };
+            let color = cli.use_color(writes_to_terminal(print_args.output.as_deref()));
+            bookmark_print(
+                print_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                color,
+            )?;
         }
         Commands::Add(add_args) => {
             let repo = setup_repo(&cli, &repository_folder)?;
-            let res = bookmark_add(add_args, repository_folder, &repo, cli.repository_url);
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_add(
+                add_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
 
             if let Err(e) = res {
                 error!("Error: {}", e);
@@ -137,7 +218,107 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         Commands::Rm(rm_args) => {
             let repo = setup_repo(&cli, &repository_folder)?;
-            let res = bookmark_rm(rm_args, repository_folder, &repo, cli.repository_url);
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_rm(
+                rm_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Move(move_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_move(
+                move_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Copy(copy_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_copy(
+                copy_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Prune(prune_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_prune(
+                prune_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::RewriteUrls(rewrite_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_rewrite_urls(
+                rewrite_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        #[cfg(feature = "check-links")]
+        Commands::CheckLinks(check_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_check_links(
+                check_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
 
             if let Err(e) = res {
                 error!("Error: {}", e);
@@ -145,18 +326,201 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         }
         Commands::Find(find_args) => {
-            let res = bookmark_find(find_args, repository_folder);
+            let mut repo = None;
+            let bookmark_file = if let Some(ref read_only_file) = read_only_file {
+                read_only_file.clone()
+            } else {
+                repo = setup_repo(&cli, &repository_folder)?;
+                select_bookmark_file(&cli, &repository_folder)?
+            };
+            let color = cli.use_color(writes_to_terminal(None));
+            let res = bookmark_find(
+                find_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                color,
+            );
+
+            match res {
+                Ok(0) if find_args.exit_code => std::process::exit(1),
+                Ok(_) => {}
+                Err(e) => {
+                    error!("Error: {}", e);
+                    std::process::exit(if find_args.exit_code { 2 } else { 1 });
+                }
+            }
+        }
+        Commands::Open(open_args) => {
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_open(open_args, repository_folder, &bookmark_file);
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Export(export_args) => {
+            let _repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            bookmark_export(export_args, repository_folder, &bookmark_file)?;
+        }
+        Commands::Import(import_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_import(
+                import_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Diff(diff_args) => {
+            let _repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            bookmark_diff(diff_args, repository_folder, &bookmark_file)?;
+        }
+        Commands::Validate => {
+            let bookmark_file = if let Some(ref read_only_file) = read_only_file {
+                read_only_file.clone()
+            } else {
+                let _repo = setup_repo(&cli, &repository_folder)?;
+                select_bookmark_file(&cli, &repository_folder)?
+            };
+            let issue_count = bookmark_validate(repository_folder, &bookmark_file)?;
+            if issue_count > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Log(log_args) => {
+            let Some(repo) = setup_repo(&cli, &repository_folder)? else {
+                return Err("No git repository (--xbel): nothing to log".into());
+            };
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            bookmark_log(log_args, &repo, &bookmark_file)?;
+        }
+        Commands::Undo(undo_args) => {
+            let Some(repo) = setup_repo(&cli, &repository_folder)? else {
+                return Err("No git repository (--xbel): nothing to undo".into());
+            };
+            let res = bookmark_undo(
+                undo_args,
+                &repo,
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
+
+            if let Err(e) = res {
+                error!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Merge(merge_args) => {
+            let repo = setup_repo(&cli, &repository_folder)?;
+            let bookmark_file = select_bookmark_file(&cli, &repository_folder)?;
+            let res = bookmark_merge(
+                merge_args,
+                repository_folder,
+                &bookmark_file,
+                repo.as_ref(),
+                &git_remote(&cli),
+                cli.repository_url.clone(),
+                &git_auth(&cli),
+            );
 
             if let Err(e) = res {
                 error!("Error: {}", e);
                 std::process::exit(1);
             }
         }
+        Commands::Completions(completions_args) => {
+            print_completions(completions_args);
+        }
     };
 
     Ok(())
 }
 
+/// Print the config file read (or the default config file path if there is none)
+fn config_path(cli: &Cli, default_config_path: &Path) {
+    match cli.config_file.as_deref() {
+        Some(config_path) => println!("{}", config_path.display()),
+        None => println!("{} (not found)", default_config_path.display()),
+    }
+}
+
+/// Print the effective settings (command line merged with the config file)
+///
+/// Secrets (token, ssh key passphrase, url password) are redacted.
+fn config_show(cli: &Cli, repository_folder: &Path) {
+    let or_none = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+    let redacted = |secret: Option<&Secret>| {
+        or_none(
+            secret
+                .filter(|s| !s.is_empty())
+                .map(|_| REDACTED.to_string()),
+        )
+    };
+    let bookmark_files = cli
+        .bookmark_files
+        .iter()
+        .map(|f| f.display().to_string())
+        .collect::<Vec<_>>();
+
+    let settings = [
+        (
+            "config_file",
+            or_none(cli.config_file.as_ref().map(|p| p.display().to_string())),
+        ),
+        (
+            "data_dir",
+            or_none(cli.data_dir.as_ref().map(|p| p.display().to_string())),
+        ),
+        ("repository_folder", repository_folder.display().to_string()),
+        (
+            "repository_url",
+            or_none(
+                cli.repository_url
+                    .as_ref()
+                    .map(|url| redact_url(url).to_string()),
+            ),
+        ),
+        ("repository_name", cli.repository_name.clone()),
+        ("repository_token", redacted(cli.repository_token.as_ref())),
+        (
+            "repository_ssh_key",
+            cli.repository_ssh_key.display().to_string(),
+        ),
+        (
+            "repository_ssh_passphrase",
+            redacted(cli.repository_ssh_passphrase.as_ref()),
+        ),
+        ("remote", cli.remote.clone()),
+        ("retries", cli.retries.to_string()),
+        (
+            "bookmark_files",
+            or_none(Some(bookmark_files.join(", ")).filter(|f| !f.is_empty())),
+        ),
+        (
+            "disable_push",
+            or_none(cli.config_disable_push.map(|d| d.to_string())),
+        ),
+        ("log_level", cli.log_level_filter().to_string()),
+    ];
+    for (name, value) in settings {
+        println!("{}: {}", name, value);
+    }
+}
+
 #[derive(Error, Debug)]
 enum InitError {
     #[error("Error: config path ({0}) already exists")]
@@ -165,6 +529,12 @@ enum InitError {
     TomlError(#[from] TomlError),
     #[error("Please provide git repository url (use floccus-cli --help for more information)")]
     GitRepositoryNotProvided,
+    #[error("Please provide a git token for a https repository url (-t)")]
+    TokenNotProvided,
+    #[error("Please provide a ssh key for a ssh repository url (-s)")]
+    SshKeyNotProvided,
+    #[error("Ssh key not found: {0}")]
+    SshKeyNotFound(PathBuf),
     #[error("Error while writing config file or creating parent folders for: {0}")]
     IoError(#[from] std::io::Error),
     #[error("Unable to get parents for: {0}")]
@@ -173,31 +543,88 @@ enum InitError {
     WriteError(#[from] AtomicWriteError),
 }
 
-fn init_app(cli: &Cli, _init_args: &InitArgs, config_path: &Path) -> Result<(), InitError> {
-    debug!("Config file path: {:?}", config_path);
+/// Repository name from the last segment of the url path (without .git)
+fn repository_name_from_url(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.rev().find(|s| !s.is_empty())?;
+    let name = segment.strip_suffix(".git").unwrap_or(segment);
+    (!name.is_empty()).then(|| name.to_string())
+}
 
-    if config_path.exists() {
-        return Err(InitError::ConfigExists(config_path.to_path_buf()));
-    }
+/// Config file content (from the config sample) for the given git repository url & credentials
+///
+/// Only the credential matching the url is written: ssh key for a ssh url, token for a https url
+fn init_config(cli: &Cli) -> Result<DocumentMut, InitError> {
+    let mut config_doc = FLOCCUS_CLI_CONFIG_SAMPLE.parse::<DocumentMut>()?;
 
-    if cli.repository_url.is_none() {
-        return Err(InitError::GitRepositoryNotProvided);
+    let repository_url = cli
+        .repository_url
+        .as_ref()
+        .ok_or(InitError::GitRepositoryNotProvided)?;
+    config_doc["git"]["repository_url"] = value(repository_url.to_string());
+    // Repository name: --name or from the url (e.g. my-bookmarks for .../my-bookmarks.git)
+    let repository_name = if cli.is_default_repository_name() {
+        repository_name_from_url(repository_url).unwrap_or_else(|| cli.repository_name.clone())
+    } else {
+        cli.repository_name.clone()
+    };
+    config_doc["git"]["repository_name"] = value(repository_name);
+
+    let git = config_doc["git"]
+        .as_table_like_mut()
+        .expect("config sample has a git table");
+    match repository_url.scheme() {
+        "ssh" => {
+            let ssh_key = cli.repository_ssh_key.as_path();
+            if ssh_key.as_os_str().is_empty() {
+                return Err(InitError::SshKeyNotProvided);
+            }
+            if !ssh_key.exists() {
+                if !cli.is_default_ssh_key() {
+                    return Err(InitError::SshKeyNotFound(ssh_key.to_path_buf()));
+                }
+                warn!(
+                    "Default ssh key {} not found (ssh-agent will be used)",
+                    ssh_key.display()
+                );
+            }
+            git.insert("repository_ssh_key", value(ssh_key.display().to_string()));
+            git.remove("repository_token");
+        }
+        "https" | "http" => {
+            let repository_token = cli
+                .repository_token
+                .as_ref()
+                .filter(|t| !t.is_empty())
+                .ok_or(InitError::TokenNotProvided)?;
+            git.insert("repository_token", value(repository_token.expose()));
+            git.remove("repository_ssh_key");
+        }
+        _ => {
+            // e.g. a local repository (file://), no credentials
+            git.remove("repository_token");
+            git.remove("repository_ssh_key");
+        }
     }
 
-    let mut config_doc = FLOCCUS_CLI_CONFIG_SAMPLE.parse::<DocumentMut>()?;
-    // println!("config: {}", config_doc);
+    Ok(config_doc)
+}
 
-    let repository_url = cli.repository_url.as_ref().unwrap().clone();
-    config_doc["git"]["repository_url"] = value(repository_url.to_string());
+fn init_app(cli: &Cli, _init_args: &InitArgs, config_path: &Path) -> Result<(), InitError> {
+    debug!("Config file path: {:?}", config_path);
 
-    if let Some(repository_token) = cli.repository_token.as_ref() {
-        config_doc["git"]["repository_token"] = value(repository_token);
+    if config_path.exists() {
+        return Err(InitError::ConfigExists(config_path.to_path_buf()));
     }
 
-    // FIXME: only for ssh url
-    config_doc["git"]["repository_ssh_key"] = value(cli.repository_ssh_key.display().to_string());
-
-    debug!("New config: {}", config_doc);
+    let config_doc = init_config(cli)?;
+    let mut logged_doc = config_doc.clone();
+    if let Some(token) = logged_doc
+        .get_mut("git")
+        .and_then(|git| git.get_mut("repository_token"))
+    {
+        *token = value(REDACTED);
+    }
+    debug!("New config: {}", logged_doc);
 
     let config_path_parent = config_path
         .parent()
@@ -212,11 +639,150 @@ fn init_app(cli: &Cli, _init_args: &InitArgs, config_path: &Path) -> Result<(),
     Ok(())
 }
 
-fn setup_repo(cli: &Cli, repository_folder: &Path) -> Result<Repository, Box<dyn Error>> {
-    let mut repository_need_pull = true; // no need to pull after a clone (for instance)
+/// Credentials used for git clone / fetch / push
+fn git_auth(cli: &Cli) -> GitAuth<'_> {
+    GitAuth {
+        ssh_key: Some(cli.repository_ssh_key.as_path()),
+        ssh_passphrase: cli.repository_ssh_passphrase.as_ref().map(Secret::expose),
+        // No ssh key explicitly configured - try ssh-agent first
+        ssh_agent: cli.is_default_ssh_key(),
+        token: cli
+            .repository_token
+            .as_ref()
+            .filter(|t| !t.is_empty())
+            .map(Secret::expose),
+    }
+}
+
+#[derive(Error, Debug)]
+enum SelectBookmarkFileError {
+    #[error("Multiple bookmark files found: {0} - please select one with --file (or set bookmark_files in config)")]
+    MultipleFiles(String),
+    #[error("Cannot list bookmark files: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Select the bookmark file (relative to the repository folder) to use
+///
+/// --xbel first, then --file, then the first file of bookmark_files (config), otherwise look for
+/// the bookmark files (*.xbel or *.xbel.gz) in the repository folder
+fn select_bookmark_file(
+    cli: &Cli,
+    repository_folder: &Path,
+) -> Result<PathBuf, SelectBookmarkFileError> {
+    if let Some(xbel_file) = cli.xbel_file.as_ref() {
+        if let Some(file_name) = xbel_file.file_name() {
+            return Ok(PathBuf::from(file_name));
+        }
+    }
+    if let Some(bookmark_file) = cli.bookmark_file.as_ref() {
+        return Ok(bookmark_file.clone());
+    }
+    if let Some(bookmark_file) = cli.bookmark_files.first() {
+        return Ok(bookmark_file.clone());
+    }
+
+    if !repository_folder.exists() {
+        return Ok(PathBuf::from(BOOKMARK_FILE_DEFAULT));
+    }
+
+    let mut bookmark_files = Vec::new();
+    for entry in std::fs::read_dir(repository_folder)? {
+        let path = entry?.path();
+        let is_xbel = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.ends_with(".xbel") || name.ends_with(".xbel.gz")
+        });
+        if path.is_file() && is_xbel {
+            if let Some(file_name) = path.file_name() {
+                bookmark_files.push(PathBuf::from(file_name));
+            }
+        }
+    }
+    bookmark_files.sort();
+
+    match bookmark_files.len() {
+        0 => Ok(PathBuf::from(BOOKMARK_FILE_DEFAULT)),
+        1 => Ok(bookmark_files.remove(0)),
+        _ => Err(SelectBookmarkFileError::MultipleFiles(
+            bookmark_files
+                .iter()
+                .map(|f| f.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )),
+    }
+}
+
+/// Git remote used for git fetch / git push
+fn git_remote(cli: &Cli) -> GitRemote<'_> {
+    GitRemote {
+        name: cli.remote.as_str(),
+        retries: cli.retries,
+        auto_merge: !cli.no_auto_merge,
+    }
+}
+
+/// Warn if the url of the git remote does not match the repository url (if provided)
+fn check_remote_url(repo: &Repository, cli: &Cli) {
+    let Some(repository_url) = cli.repository_url.as_ref() else {
+        return;
+    };
+    let Some(remote_url) = repo
+        .find_remote(cli.remote.as_str())
+        .ok()
+        .and_then(|remote| remote.url().map(str::to_string))
+    else {
+        return;
+    };
+    if !is_same_repository_url(repository_url, &remote_url) {
+        warn!(
+            "Git remote {} url ({}) does not match the repository url ({}) - use --repository (or --name) to choose another repository folder",
+            cli.remote,
+            Url::parse(&remote_url).map_or(remote_url.clone(), |url| repository_url_key(&url)),
+            repository_url_key(repository_url)
+        );
+    }
+}
+
+/// Repository url without credentials (user / token), trailing "/" and ".git"
+fn repository_url_key(url: &Url) -> String {
+    format!(
+        "{}://{}{}{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default(),
+        url.port()
+            .map(|port| format!(":{}", port))
+            .unwrap_or_default(),
+        url.path().trim_end_matches('/').trim_end_matches(".git")
+    )
+}
+
+/// Same git repository url (see `repository_url_key`), e.g.
+/// https://TOKEN@github.com/me/bookmarks.git and https://github.com/me/bookmarks
+fn is_same_repository_url(url: &Url, other: &str) -> bool {
+    Url::parse(other).is_ok_and(|other| repository_url_key(url) == repository_url_key(&other))
+}
+
+/// Clone or pull (git pull) the git repository (or nothing if --xbel is used)
+fn setup_repo(cli: &Cli, repository_folder: &Path) -> Result<Option<Repository>, Box<dyn Error>> {
+    if cli.xbel_file.is_some() {
+        info!("No git repository (--xbel)");
+        return Ok(None);
+    }
+
+    // no need to pull after a clone (for instance) or with --no-pull
+    let mut repository_need_pull = !cli.no_pull;
+    if cli.no_pull {
+        info!("Not pulling git repository (--no-pull)");
+    }
 
-    let repo = if !repository_folder.exists() {
-        // repository folder does not exist - need to clone
+    let is_empty_folder = |path: &Path| {
+        path.read_dir()
+            .is_ok_and(|mut entries| entries.next().is_none())
+    };
+    let repo = if !repository_folder.exists() || is_empty_folder(repository_folder) {
+        // repository folder does not exist (or is empty) - need to clone
 
         // first check if repository url is provided
         if cli.repository_url.is_none() {
@@ -224,100 +790,287 @@ fn setup_repo(cli: &Cli, repository_folder: &Path) -> Result<Repository, Box<dyn
         }
         let repository_url = cli.repository_url.as_ref().unwrap();
 
-        info!("Cloning repository: {}", repository_url);
+        info!("Cloning repository: {}", redact_url(repository_url));
         let repo = git_clone(
             repository_url,
             repository_folder,
-            Some(cli.repository_ssh_key.as_path()),
+            cli.remote.as_str(),
+            &git_auth(cli),
         )?;
         repository_need_pull = false;
         repo
     } else {
-        Repository::open(repository_folder)?
+        let repo = match Repository::open(repository_folder) {
+            Ok(repo) => repo,
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                return Err(format!(
+                    "{} is not a git repository (and is not empty) - use --repository (or --name) to choose another folder, or remove its content so the repository can be cloned into it",
+                    repository_folder.display()
+                )
+                .into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        check_remote_url(&repo, cli);
+        repo
     };
 
     // ~ git pull
     if repository_need_pull {
+        let bookmark_file = select_bookmark_file(cli, repository_folder)?;
+        if git_is_dirty(&repo, &bookmark_file)? {
+            if cli.force {
+                warn!(
+                    "Local changes to {} will be overwritten (--force)",
+                    bookmark_file.display()
+                );
+            } else {
+                return Err(format!(
+                    "{} has local uncommitted changes (in {}) - commit or revert them, or use --force to discard them",
+                    bookmark_file.display(),
+                    repository_folder.display()
+                )
+                .into());
+            }
+        }
+
         // TODO: get current branch name from repo?
-        let mut remote = repo.find_remote("origin")?;
+        let remote_ = git_remote(cli);
+        let mut remote = git_find_remote(&repo, remote_.name)?;
         let remote_branch = "main";
-        let fetch_commit = git_fetch(&repo, &[remote_branch], &mut remote)?;
-        git_merge(&repo, remote_branch, fetch_commit)?;
+        let fetch_res = git_fetch(
+            &repo,
+            &[remote_branch],
+            &mut remote,
+            remote_.retries,
+            &git_auth(cli),
+        );
+        match fetch_res {
+            Ok(fetch_commit) => git_merge(&repo, remote_branch, fetch_commit, cli.force)?,
+            // Nothing to fetch from an empty remote repository
+            Err(e) if git_is_empty(&repo)? => {
+                warn!("Cannot fetch (remote repository is probably empty): {}", e);
+            }
+            Err(e) => return Err(e.into()),
+        }
     }
 
-    {
-        // Get the HEAD reference
-        let head = &repo.head()?;
+    if git_is_empty(&repo)? {
+        info!("Repository is empty (no commit yet)");
+    } else {
         // Get the commit associated with the HEAD reference
-        let commit = &repo.find_commit(head.target().unwrap())?;
+        let commit = &repo.head()?.peel_to_commit()?;
         info!("Repository at commit: {:?}: {:?}", commit, commit.message());
     }
 
-    Ok(repo)
+    Ok(Some(repo))
 }
 
-fn bookmark_print(_print_args: &PrintArgs, repository_folder: PathBuf) -> anyhow::Result<()> {
-    const FOLDER_EMOTICON: &str = "\u{1F4C1}";
-    const _FOLDER_LINK: &str = "\u{1F310}";
-    const FOLDER_LINK1: &str = "\u{1F517}";
-    const INDENTER: fn(usize) -> String = |indent_spaces| " ".repeat(indent_spaces);
+fn bookmark_print(
+    print_args: &PrintArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    color: bool,
+) -> anyhow::Result<()> {
+    let xbel = if let Some(rev) = print_args.since.as_deref() {
+        xbel_at_revision(repo, rev, bookmark_file)?
+    } else {
+        let bookmark_file_path = repository_folder.join(bookmark_file);
+        let bookmark_file_path_clone = bookmark_file_path.clone();
+        xbel_from_file(&bookmark_file_path).with_context(|| {
+            format!(
+                "Error while parsing: {}",
+                bookmark_file_path_clone.to_string_lossy()
+            )
+        })?
+    };
 
-    let bookmark_file_path = repository_folder.join("bookmarks.xbel");
-    let bookmark_file_path_clone = bookmark_file_path.clone();
-    let xbel = Xbel::try_from_file(bookmark_file_path).with_context(|| {
-        format!(
-            "Error while parsing: {}",
-            bookmark_file_path_clone.to_string_lossy()
-        )
-    })?;
-
-    let xbel_it = XbelNestingIterator::new(&xbel);
-    let mut indent_spaces = 0;
-    for item in xbel_it {
-        match item {
-            XbelItemOrEnd::End(_) => indent_spaces -= 2,
-            XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
-                println!(
-                    "{}[{FOLDER_EMOTICON} {}] {}",
-                    INDENTER(indent_spaces),
-                    f.id,
-                    f.title.text
-                );
-                indent_spaces += 2;
-            }
-            XbelItemOrEnd::Item(XbelItem::Bookmark(b)) => {
-                let indent = INDENTER(indent_spaces);
-                println!("{}[{FOLDER_LINK1} {}] {}", indent, b.id, b.title.text);
-                println!("{}- {}", indent, b.href);
-            }
+    let xbel_it = match print_args.under.as_ref().map(XbelPath::from) {
+        None | Some(XbelPath::Root) => XbelNestingIterator::new(&xbel),
+        Some(xbel_path) => {
+            let (item_index, items) = xbel
+                .get_items(&xbel_path)
+                .ok_or_else(|| anyhow!("Cannot find anything in Xbel matching: {}", xbel_path))?;
+            XbelNestingIterator::from_item(&items[item_index])
         }
-    }
+    };
+    let options = PrintOptions {
+        max_depth: print_args.depth,
+        json_pretty: print_args
+            .json_args
+            .is_pretty(writes_to_terminal(print_args.output.as_deref())),
+        color,
+    };
+    let mut output = output_writer(print_args.output.as_deref())?;
+    print_items(xbel_it, print_args.format, &options, &mut output)?;
+    output.flush()?;
 
     Ok(())
 }
 
+/// Bookmark file when reading from stdin (STDIN_PATH) or from a url (read only commands)
+fn read_only_bookmark_file(cli: &Cli) -> Option<PathBuf> {
+    if cli.stdin {
+        return Some(PathBuf::from(STDIN_PATH));
+    }
+    #[cfg(feature = "fetch-xbel")]
+    if let Some(ref url) = cli.xbel_url {
+        return Some(PathBuf::from(url.as_str()));
+    }
+    None
+}
+
+#[derive(Error, Debug)]
+enum XbelFromFileError {
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[cfg(feature = "fetch-xbel")]
+    #[error(transparent)]
+    FetchError(#[from] web::FetchXbelError),
+}
+
+/// Read a Xbel file, or from stdin if the file path is STDIN_PATH (--stdin), or over http if the
+/// file path is a http(s) url (--from-url)
+fn xbel_from_file(file_path: &Path) -> Result<Xbel, XbelFromFileError> {
+    if file_path == Path::new(STDIN_PATH) {
+        return Ok(Xbel::try_from_reader(std::io::stdin().lock())?);
+    }
+    // Note: a url is only used as file path with --from-url (no repository folder to join with)
+    #[cfg(feature = "fetch-xbel")]
+    if let Some(url) = file_path
+        .to_str()
+        .filter(|p| p.starts_with("http://") || p.starts_with("https://"))
+    {
+        return Ok(web::fetch_xbel(url)?);
+    }
+    Ok(Xbel::try_from_file(file_path)?)
+}
+
+#[derive(Error, Debug)]
+enum XbelAtRevisionError {
+    #[error(
+        "--since requires a git repository (not available with --xbel, --stdin or --from-url)"
+    )]
+    NoRepository,
+    #[error(transparent)]
+    GitShowError(#[from] GitShowError),
+    #[error("Cannot parse bookmark file at git revision {0}: {1}")]
+    XbelReadError(String, XbelError),
+}
+
+/// Read the bookmark file at a git revision (instead of the working tree)
+fn xbel_at_revision(
+    repo: Option<&Repository>,
+    rev: &str,
+    bookmark_file: &Path,
+) -> Result<Xbel, XbelAtRevisionError> {
+    let repo = repo.ok_or(XbelAtRevisionError::NoRepository)?;
+    let content = git_show_file(repo, rev, bookmark_file)?;
+    Xbel::try_from_reader(content.as_slice())
+        .map_err(|e| XbelAtRevisionError::XbelReadError(rev.to_string(), e))
+}
+
+/// Writer to the given output file (created or truncated) or to stdout if None
+fn output_writer(output: Option<&Path>) -> anyhow::Result<Box<dyn Write>> {
+    match output {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("Cannot create output file: {}", path.display()))?;
+            Ok(Box::new(std::io::BufWriter::new(file)))
+        }
+        None => Ok(Box::new(std::io::stdout().lock())),
+    }
+}
+
+/// Is the output (see `output_writer`) a terminal
+fn writes_to_terminal(output: Option<&Path>) -> bool {
+    output.is_none() && std::io::stdout().is_terminal()
+}
+
 impl From<&Under> for XbelPath {
     fn from(value: &Under) -> Self {
         match value {
-            Under::Root => XbelPath::Root,
+            Under::Root(_) => XbelPath::Root,
             Under::Id(id, _) => XbelPath::Id(*id),
             Under::Folder(p) => XbelPath::Path(p.clone()),
         }
     }
 }
 
+impl From<&Under> for Placement {
+    fn from(value: &Under) -> Self {
+        match value {
+            Under::Root(placement) | Under::Id(_, placement) => placement.clone(),
+            Under::Folder(_) => Placement::InFolderAppend,
+        }
+    }
+}
+
+/// Urls of the bookmarks in the given scope: the whole Xbel or the folder where bookmarks would be
+/// added (empty if not found)
+fn existing_urls(xbel: &Xbel, under: &Under, scope: UniqueScope) -> HashSet<String> {
+    let bookmark_url = |item: &XbelItem| match item {
+        XbelItem::Bookmark(b) => Some(b.href.clone()),
+        _ => None,
+    };
+    match scope {
+        UniqueScope::Global => xbel.into_iter().filter_map(bookmark_url).collect(),
+        UniqueScope::Folder => {
+            let folder_items = match (under, xbel.get_items(&XbelPath::from(under))) {
+                (
+                    Under::Root(_) | Under::Id(_, Placement::Before | Placement::After),
+                    Some((_, items)),
+                ) => Some(items),
+                (_, Some((index, items))) => match &items[index] {
+                    XbelItem::Folder(f) => Some(&f.items),
+                    _ => None,
+                },
+                (_, None) => None,
+            };
+            folder_items
+                .into_iter()
+                .flatten()
+                .filter_map(bookmark_url)
+                .collect()
+        }
+    }
+}
+
+/// Warn if the highestId stored in the Xbel file is lower than the ids found in the file
+/// (usually after a manual edit) as Floccus could reuse ids
+fn warn_on_stale_highest_id(xbel: &Xbel) {
+    if let Some(stored_highest_id) = xbel.stored_highest_id() {
+        let highest_id = xbel.get_highest_id();
+        if stored_highest_id < highest_id {
+            warn!(
+                "Stored highestId ({}) is lower than the highest id found in the bookmark file ({})",
+                stored_highest_id, highest_id
+            );
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 enum BookmarkAddError {
     #[error("Error: please provide git repository url (or use --disable-push)")]
     PushWithoutUrl,
     #[error(transparent)]
     XbelReadError(#[from] XbelError),
-    #[error("Cannot find anything in Xbel matching: {0}")]
-    XbelPathNotFound(XbelPath),
-    #[error("Item found with id: {0} but it is not a folder")]
-    NotaFolder(String),
+    #[error(transparent)]
+    InsertError(#[from] XbelInsertError),
+    #[error("An item with id: {0} already exists - use --overwrite to replace it")]
+    IdAlreadyExists(String),
+    #[error("Item found with id: {0} is a folder and cannot be overwritten")]
+    CannotOverwriteFolder(String),
+    #[error("Malformed line {0}: {1}")]
+    MalformedLine(usize, String),
+    #[error("Invalid url: {0} ({1}) - use --allow-invalid to add it anyway")]
+    InvalidUrl(String, String),
     // TODO: remap error GitAddError, GitCommitError ...
     #[error(transparent)]
+    BatchFailed(#[from] BatchFailedError),
+    #[error(transparent)]
     GitError(#[from] git2::Error),
     #[error(transparent)]
     WriteError(#[from] AtomicWriteError),
@@ -328,81 +1081,291 @@ enum BookmarkAddError {
 fn bookmark_add(
     add_args: &AddArgs,
     repository_folder: PathBuf,
-    repo: &Repository,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
     repository_url: Option<Url>,
+    auth: &GitAuth,
 ) -> Result<(), BookmarkAddError> {
-    if add_args.disable_push == Some(false) && repository_url.is_none() {
+    let push = add_args.push_args.should_push();
+    if push && repository_url.is_none() {
         return Err(BookmarkAddError::PushWithoutUrl);
     }
 
+    // Bookmarks to add: (url, title)
+    let mut report = BatchReport::default();
+    let mut to_add = if let Some(from_file) = add_args.from_file.as_ref() {
+        let content = std::fs::read_to_string(from_file)
+            .with_context(|| format!("Error while reading: {}", from_file.display()))?;
+        let (to_add, malformed) = parse_bookmark_list(content.as_str(), add_args.allow_invalid);
+        for (line_number, line) in malformed.iter() {
+            if add_args.strict {
+                return Err(BookmarkAddError::MalformedLine(*line_number, line.clone()));
+            }
+            if add_args.keep_going {
+                report
+                    .failures
+                    .push(format!("Malformed line {}: {}", line_number, line));
+                continue;
+            }
+            warn!(
+                "Ignoring malformed line {} in {}: {}",
+                line_number,
+                from_file.display(),
+                line
+            );
+        }
+        to_add
+    } else {
+        // Note: url is required by clap unless --from-file is provided
+        let url = add_args.url.clone().unwrap_or_default();
+        if !add_args.allow_invalid {
+            validate_url(url.as_str()).map_err(|e| BookmarkAddError::InvalidUrl(url.clone(), e))?;
+        }
+        let title = match add_args.title.as_ref() {
+            Some(title) => title.clone(),
+            None => default_title(url.as_str(), !add_args.no_fetch_title),
+        };
+        vec![(url, title)]
+    };
+
     // Read xbel
-    let bookmark_file_path_xbel = PathBuf::from("bookmarks.xbel");
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
     let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
     let bookmark_file_path_clone = bookmark_file_path.clone();
-    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
-        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path_clone))?;
-
-    // Build the bookmark
-    let bookmark = xbel.new_bookmark(add_args.url.as_str(), add_args.title.as_str());
+    let mut xbel = if bookmark_file_path.exists() {
+        Xbel::try_from_file(&bookmark_file_path)
+            .with_context(|| format!("Error while reading: {:?}", bookmark_file_path_clone))?
+    } else {
+        // e.g. first add after init in an empty repository
+        info!(
+            "Bookmark file {} does not exist, creating it",
+            bookmark_file_path.display()
+        );
+        Xbel::new(None)
+    };
+    warn_on_stale_highest_id(&xbel);
+
+    let under = add_args.under();
+    if add_args.unique {
+        let mut existing = existing_urls(&xbel, &under, add_args.unique_scope);
+        // Note: also skip an url listed twice (--from-file)
+        to_add.retain(|(url, _title)| {
+            let is_new = existing.insert(url.clone());
+            if !is_new {
+                println!("Skipping {}: already bookmarked", url);
+            }
+            is_new
+        });
+        if to_add.is_empty() {
+            return Ok(report.finish(add_args.keep_going)?);
+        }
+    }
 
-    // Find where to put the bookmark
-    let xbel_path = XbelPath::from(&add_args.under);
-    let (item_index, items) = xbel
-        .get_items_mut(&xbel_path)
-        .ok_or(BookmarkAddError::XbelPathNotFound(xbel_path.clone()))?;
+    // Create missing folders first (folder ids before bookmark ids)
+    let created_folder_ids = match &under {
+        Under::Folder(path) if add_args.parents => xbel.create_folder_path(path),
+        _ => vec![],
+    };
 
-    match xbel_path {
-        XbelPath::Root => items.push(bookmark),
-        XbelPath::Id(id) => {
-            if let Under::Id(_id, placement) = &add_args.under {
-                match placement {
-                    Placement::Before => {
-                        items.insert(item_index, bookmark);
-                    }
-                    Placement::After => {
-                        items.insert(item_index.saturating_add(1), bookmark);
-                    }
-                    Placement::InFolderPrepend => {
-                        if let XbelItem::Folder(f) = &mut items[item_index] {
-                            f.items.insert(0, bookmark)
-                        } else {
-                            return Err(BookmarkAddError::NotaFolder(id.to_string()));
-                        }
-                    }
-                    Placement::InFolderAppend => {
-                        if let XbelItem::Folder(f) = &mut items[item_index] {
-                            f.items.push(bookmark)
-                        } else {
-                            return Err(BookmarkAddError::NotaFolder(id.to_string()));
-                        }
+    // Build the bookmarks (with the given id or consecutive ids)
+    // Note: with --overwrite, the bookmark is replaced in place unless --under is given (and is
+    //       not relative to the replaced bookmark)
+    let mut replace_id = None;
+    let mut bookmarks = if let Some(id) = add_args.id {
+        match xbel.get_items(&XbelPath::Id(id)) {
+            Some((item_index, items)) if !add_args.overwrite => {
+                return Err(BookmarkAddError::IdAlreadyExists(
+                    items[item_index].get_id().clone(),
+                ));
+            }
+            Some((item_index, items)) => {
+                if let XbelItem::Folder(f) = &items[item_index] {
+                    return Err(BookmarkAddError::CannotOverwriteFolder(f.id.clone()));
+                }
+                info!("Overwriting bookmark with id: {}", id);
+                match &add_args.under {
+                    None => replace_id = Some(id),
+                    Some(Under::Id(under_id, _)) if *under_id == id => replace_id = Some(id),
+                    Some(_) => {
+                        xbel.remove_by_id(id);
                     }
                 }
-            } else {
-                unreachable!()
             }
+            None => {}
         }
-        XbelPath::Path(_s) => {
-            if let XbelItem::Folder(f) = &mut items[item_index] {
-                f.items.push(bookmark)
-            } else {
-                return Err(BookmarkAddError::NotaFolder(
-                    items[item_index].get_id().to_string(),
-                ));
+        // Note: --id conflicts with --from-file (a single bookmark to add)
+        to_add
+            .iter()
+            .map(|(url, title)| XbelItem::new_bookmark(id.to_string().as_str(), url, title))
+            .collect::<Vec<_>>()
+    } else {
+        xbel.new_bookmarks(
+            to_add
+                .iter()
+                .map(|(url, title)| (url.as_str(), title.as_str())),
+        )
+    };
+    for bookmark in bookmarks.iter_mut() {
+        if let XbelItem::Bookmark(b) = bookmark {
+            b.set_tags(&add_args.tags);
+        }
+    }
+    info!(
+        "Adding {} {}",
+        bookmarks.len(),
+        pluralize("bookmark", bookmarks.len())
+    );
+    let bookmark_ids = bookmarks
+        .iter()
+        .filter_map(XbelItem::numeric_id)
+        .collect::<Vec<_>>();
+    report.succeeded = bookmarks.len();
+
+    // Insert the bookmarks
+    if let Some(id) = replace_id {
+        // Note: --id conflicts with --from-file (a single bookmark)
+        for bookmark in bookmarks {
+            xbel.replace_subtree(id, bookmark);
+        }
+    } else {
+        xbel.insert_all_at_path(&XbelPath::from(&under), bookmarks, Placement::from(&under))?;
+    }
+
+    if add_args.dry_run {
+        for id in created_folder_ids {
+            if let Some((item_index, items)) = xbel.get_items(&XbelPath::Id(id)) {
+                let folder = &items[item_index];
+                println!(
+                    "[Dry run] creating folder: [{}] {}",
+                    id,
+                    folder.get_title().text
+                );
             }
         }
-    };
+        for id in bookmark_ids {
+            let xbel_path = XbelPath::Id(id);
+            if let Some((item_index, items)) = xbel.get_items(&xbel_path) {
+                let item = &items[item_index];
+                let folder_path = folder_path_of(&xbel, item).unwrap_or_default();
+                println!(
+                    "[Dry run] adding bookmark: {:?} in {} at position {}",
+                    item,
+                    if folder_path.is_empty() {
+                        "root level"
+                    } else {
+                        folder_path.as_str()
+                    },
+                    item_index
+                );
+            }
+        }
+        return Ok(report.finish(add_args.keep_going)?);
+    }
 
     debug!("xbel: {:?}", xbel);
     // Write to file locally
-    atomic_write(bookmark_file_path.as_path(), xbel.to_string(), false)?;
+    if add_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            add_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
 
-    if add_args.disable_push == Some(false) {
-        git_push(repo, bookmark_file_path_xbel.as_path())?;
+    for id in created_folder_ids {
+        println!("Created folder with id: {}", id);
     }
+    for id in bookmark_ids {
+        println!("Added bookmark with id: {}", id);
+    }
+
+    Ok(report.finish(add_args.keep_going)?)
+}
 
+/// Title for a bookmark added without title: the web page title (if fetched) or the url
+#[cfg_attr(not(feature = "fetch-title"), allow(unused_variables))]
+fn default_title(url: &str, fetch_title: bool) -> String {
+    #[cfg(feature = "fetch-title")]
+    if fetch_title {
+        match web::fetch_title(url) {
+            Ok(title) => return title,
+            Err(e) => warn!("Cannot fetch title of {}: {} - using url as title", url, e),
+        }
+    }
+
+    url.to_string()
+}
+
+/// Url schemes accepted for new bookmarks (unless --allow-invalid)
+const URL_SCHEMES_ALLOWED: [&str; 4] = ["http", "https", "ftp", "file"];
+
+/// Check that an url can be parsed and uses a supported scheme
+///
+/// Custom schemes are accepted for hierarchical urls (e.g. obsidian://open?vault=notes) but not
+/// for urls without a '//' (e.g. javascript:alert(1)). The url is only checked (never rewritten),
+/// percent-encoding is kept as is.
+fn validate_url(url: &str) -> Result<(), String> {
+    let url_ = Url::parse(url).map_err(|e| e.to_string())?;
+    if !URL_SCHEMES_ALLOWED.contains(&url_.scheme()) && url_.cannot_be_a_base() {
+        return Err(format!(
+            "unsupported scheme: {} (expected one of: {} or a custom scheme url with '//')",
+            url_.scheme(),
+            URL_SCHEMES_ALLOWED.join(", ")
+        ));
+    }
     Ok(())
 }
 
+/// A bookmark to add: (url, title)
+type BookmarkEntry = (String, String);
+
+/// Parse a bookmark list: one `url<TAB>title` (or only `url`, title is then the url) per line
+///
+/// Blank lines and lines starting with '#' are ignored.
+/// Returns the bookmarks (url, title) and the malformed lines (line number, line)
+fn parse_bookmark_list(
+    content: &str,
+    allow_invalid: bool,
+) -> (Vec<BookmarkEntry>, Vec<(usize, String)>) {
+    let mut bookmarks = vec![];
+    let mut malformed = vec![];
+
+    for (index, line) in content.lines().enumerate() {
+        let line_trimmed = line.trim();
+        if line_trimmed.is_empty() || line_trimmed.starts_with('#') {
+            continue;
+        }
+
+        let (url, title) = match line_trimmed.split_once('\t') {
+            Some((url, title)) => (url.trim(), title.trim()),
+            None => (line_trimmed, line_trimmed),
+        };
+
+        if url.contains(char::is_whitespace) || (!allow_invalid && validate_url(url).is_err()) {
+            malformed.push((index + 1, line.to_string()));
+            continue;
+        }
+
+        let title = if title.is_empty() { url } else { title };
+        bookmarks.push((url.to_string(), title.to_string()));
+    }
+
+    (bookmarks, malformed)
+}
+
 #[derive(Error, Debug)]
 enum BookmarkRemoveError {
     #[error("Error: please provide git repository url (or use --disable-push)")]
@@ -411,87 +1374,746 @@ enum BookmarkRemoveError {
     XbelReadError(#[from] XbelError),
     #[error("Cannot find anything in Xbel matching: {0}")]
     XbelPathNotFound(XbelPath),
+    #[error("Cannot remove root level (use an id or a path)")]
+    RootNotAnItem,
+    #[error("{0} - nothing removed (use --keep-going to remove the other bookmarks)")]
+    ItemFailed(String),
+    #[error("Invalid glob pattern: {0}")]
+    GlobError(#[from] globset::Error),
     // // TODO: remap error GitAddError, GitCommitError ...
     #[error(transparent)]
+    BatchFailed(#[from] BatchFailedError),
+    #[error(transparent)]
     GitError(#[from] git2::Error),
     #[error(transparent)]
     WriteError(#[from] AtomicWriteError),
     #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
 fn bookmark_rm(
     rm_args: &RemoveArgs,
     repository_folder: PathBuf,
-    repo: &Repository,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
     repository_url: Option<Url>,
+    auth: &GitAuth,
 ) -> Result<(), BookmarkRemoveError> {
-    if rm_args.disable_push == Some(false) && repository_url.is_none() {
+    let push = rm_args.push_args.should_push();
+    if push && repository_url.is_none() {
         return Err(BookmarkRemoveError::PushWithoutUrl);
     }
 
     // Read xbel file
-    let bookmark_file_path_xbel = PathBuf::from("bookmarks.xbel");
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
     let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
     // let bookmark_file_path_clone = bookmark_file_path.clone();
     let mut xbel = Xbel::try_from_file(&bookmark_file_path)
         .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
 
-    // Find where to put the bookmark
-    let xbel_path = XbelPath::from(&rm_args.under);
+    let mut report = BatchReport::default();
+    let removed = match &rm_args.under {
+        Some(under) => rm_item(&mut xbel, under, rm_args)?,
+        None => rm_matching(&mut xbel, rm_args, &mut report)?,
+    };
+    if !removed {
+        return Ok(report.finish(rm_args.keep_going)?);
+    }
+
+    // Write to file locally
+    if rm_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            rm_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(report.finish(rm_args.keep_going)?)
+}
+
+/// Remove a bookmark or a folder (rm -i), returns false if nothing has been removed (dry run)
+fn rm_item(
+    xbel: &mut Xbel,
+    under: &Under,
+    rm_args: &RemoveArgs,
+) -> Result<bool, BookmarkRemoveError> {
+    let xbel_path = XbelPath::from(under);
+    if matches!(xbel_path, XbelPath::Root) {
+        return Err(BookmarkRemoveError::RootNotAnItem);
+    }
     let (item_index, items) = xbel
         .get_items_mut(&xbel_path)
         .ok_or(BookmarkRemoveError::XbelPathNotFound(xbel_path.clone()))?;
 
-    match xbel_path {
-        XbelPath::Root => {
-            // TODO: return Error
-            unimplemented!()
-        }
-        XbelPath::Id(_id) => {
-            if rm_args.dry_run {
-                match &items[item_index] {
-                    XbelItem::Folder(f) => {
-                        // XXX:
-                        // Folder Debug print folder + all children recursively
-                        // Maybe we could print only the folder + children count ?
-                        println!("[Dry run] removing folder: {:?}", f);
-                    }
-                    XbelItem::Bookmark(b) => {
-                        println!("[Dry run] removing bookmark: {:?}", b);
-                    }
+    if rm_args.dry_run {
+        match &items[item_index] {
+            XbelItem::Folder(f) => {
+                let (folders, bookmarks) = f.count_descendants();
+                println!(
+                    "[Dry run] removing folder: [{}] {} ({} {}, {} {})",
+                    f.id,
+                    f.title.text,
+                    folders,
+                    pluralize("folder", folders),
+                    bookmarks,
+                    pluralize("bookmark", bookmarks)
+                );
+                if rm_args.list {
+                    // Note: the folder itself is at depth 0
+                    let options = PrintOptions {
+                        max_depth: rm_args.depth.map(|depth| depth + 1),
+                        ..Default::default()
+                    };
+                    print_items(
+                        XbelNestingIterator::from_item(&items[item_index]),
+                        PrintFormat::Tree,
+                        &options,
+                        &mut std::io::stdout().lock(),
+                    )?;
                 }
-            } else {
-                info!("Removing: {:?}", items.get(item_index));
-                items.remove(item_index);
             }
+            XbelItem::Bookmark(b) => {
+                println!(
+                    "[Dry run] removing bookmark: [{}] {} ({})",
+                    b.id, b.title.text, b.href
+                );
+            }
+            XbelItem::Alias { reference } => {
+                println!("[Dry run] removing alias of: {}", reference);
+            }
+        }
+        return Ok(false);
+    }
+
+    match xbel_path {
+        XbelPath::Root => {
+            return Err(BookmarkRemoveError::RootNotAnItem);
+        }
+        XbelPath::Id(id) => {
+            let removed = xbel.remove_by_id(id);
+            info!("Removing: {:?}", removed);
         }
         XbelPath::Path(_s) => {
-            if rm_args.dry_run {
-                match &items[item_index] {
-                    XbelItem::Folder(f) => {
-                        println!(
-                            "[Dry run] removing folder: {:?} with {} children",
-                            f.title,
-                            f.items.len()
-                        );
-                    }
-                    XbelItem::Bookmark(b) => {
-                        println!("[Dry run] removing bookmark: {:?}", b);
-                    }
+            info!("Removing: {:?}", items.get(item_index));
+            items.remove(item_index);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Remove all the bookmarks matching --match and --host (see find), returns false if nothing
+/// has been removed (dry run or no match)
+///
+/// A matching bookmark without a numeric id cannot be removed: with --keep-going, it is added to
+/// the report failures, otherwise nothing is removed (error).
+fn rm_matching(
+    xbel: &mut Xbel,
+    rm_args: &RemoveArgs,
+    report: &mut BatchReport,
+) -> Result<bool, BookmarkRemoveError> {
+    let glob = match (rm_args.match_.as_deref(), rm_args.glob) {
+        (Some(pattern), true) => Some(find_glob(pattern)?),
+        _ => None,
+    };
+    let mut ids = vec![];
+    for item in xbel
+        .into_iter()
+        .filter(|item| matches!(item, XbelItem::Bookmark(_)))
+        .filter(|item| {
+            rm_args.match_.as_deref().map_or(true, |to_find| {
+                find_text_match(item, to_find, glob.as_ref(), &FindWhere::All)
+            })
+        })
+        .filter(|item| {
+            rm_args.host.is_none() || find_url_match(item, rm_args.host.as_deref(), None)
+        })
+    {
+        match item.numeric_id() {
+            Some(id) => ids.push(id),
+            None => {
+                let failure = format!(
+                    "Cannot remove bookmark without a numeric id: [{}] {}",
+                    item.get_id(),
+                    item.get_title().text
+                );
+                if !rm_args.keep_going {
+                    return Err(BookmarkRemoveError::ItemFailed(failure));
                 }
-            } else {
-                info!("Removing: {:?}", items.get(item_index));
-                items.remove(item_index);
+                report.failures.push(failure);
+            }
+        }
+    }
+
+    let removed = remove_bookmarks(xbel, &ids, rm_args.dry_run);
+    report.succeeded = removed;
+    if removed == 0 {
+        println!("Found 0 bookmark");
+        return Ok(false);
+    }
+    if rm_args.dry_run {
+        return Ok(false);
+    }
+    println!("Removed {} {}", removed, pluralize("bookmark", removed));
+    Ok(true)
+}
+
+/// Remove bookmarks given their ids (only printed with `dry_run`), returns the number of
+/// bookmarks removed
+fn remove_bookmarks(xbel: &mut Xbel, ids: &[u64], dry_run: bool) -> usize {
+    let mut removed = 0;
+    for id in ids {
+        if let Some(XbelItem::Bookmark(b)) = xbel.remove_by_id(*id) {
+            if dry_run {
+                println!(
+                    "[Dry run] removing bookmark: [{}] {} ({})",
+                    b.id, b.title.text, b.href
+                );
             }
+            removed += 1;
         }
     }
+    removed
+}
+
+#[derive(Error, Debug)]
+enum BookmarkMoveError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error("Cannot find anything in Xbel matching: {0}")]
+    XbelPathNotFound(XbelPath),
+    #[error("Cannot move root level (use an id or a path)")]
+    RootNotAnItem,
+    #[error(transparent)]
+    MoveError(#[from] XbelMoveError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn bookmark_move(
+    move_args: &MoveArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkMoveError> {
+    let push = move_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkMoveError::PushWithoutUrl);
+    }
+
+    // Read xbel file
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+
+    // Item to move (id)
+    let item_path = XbelPath::from(&move_args.item);
+    if matches!(item_path, XbelPath::Root) {
+        return Err(BookmarkMoveError::RootNotAnItem);
+    }
+    let id = xbel
+        .get_items(&item_path)
+        .and_then(|(item_index, items)| items[item_index].numeric_id())
+        .ok_or(BookmarkMoveError::XbelPathNotFound(item_path))?;
+
+    // Where to move it
+    let target = XbelPath::from(&move_args.under);
+    xbel.move_item(id, &target, Placement::from(&move_args.under))?;
+    info!("Moved item with id: {} under: {}", id, target);
+
+    // Write to file locally
+    if move_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            move_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+enum BookmarkCopyError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error("Cannot find anything in Xbel matching: {0}")]
+    XbelPathNotFound(XbelPath),
+    #[error("Cannot copy root level (use an id or a path)")]
+    RootNotAnItem,
+    #[error(transparent)]
+    CopyError(#[from] XbelMoveError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn bookmark_copy(
+    copy_args: &CopyArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkCopyError> {
+    let push = copy_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkCopyError::PushWithoutUrl);
+    }
+
+    // Read xbel file
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+
+    // Item to copy (id)
+    let item_path = XbelPath::from(&copy_args.item);
+    if matches!(item_path, XbelPath::Root) {
+        return Err(BookmarkCopyError::RootNotAnItem);
+    }
+    let id = xbel
+        .get_items(&item_path)
+        .and_then(|(item_index, items)| items[item_index].numeric_id())
+        .ok_or(BookmarkCopyError::XbelPathNotFound(item_path))?;
+
+    // Where to copy it
+    let target = XbelPath::from(&copy_args.under);
+    let mapping = xbel.copy_item(id, &target, Placement::from(&copy_args.under))?;
+    info!(
+        "Copied item with id: {} (new id: {}, {} item(s) copied) under: {}",
+        id,
+        mapping[&id.to_string()],
+        mapping.len(),
+        target
+    );
+
+    // Write to file locally
+    if copy_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            copy_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+enum BookmarkPruneError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn bookmark_prune(
+    prune_args: &PruneArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkPruneError> {
+    let push = prune_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkPruneError::PushWithoutUrl);
+    }
+
+    // Read xbel file
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+
+    if prune_args.dry_run {
+        let mut pruned = xbel.clone();
+        for id in pruned.prune_empty_folders() {
+            let title = id
+                .parse::<u64>()
+                .ok()
+                .and_then(|id| xbel.get_folder(id))
+                .map(|f| f.title.text.as_str())
+                .unwrap_or_default();
+            println!("[Dry run] removing folder: [{}] {}", id, title);
+        }
+        return Ok(());
+    }
+
+    let removed = xbel.prune_empty_folders();
+    if removed.is_empty() {
+        println!("No empty folders");
+        if !prune_args.write_args.normalize {
+            return Ok(());
+        }
+    } else {
+        info!("Removed folders: {:?}", removed);
+        println!(
+            "Removed {} empty {}",
+            removed.len(),
+            pluralize("folder", removed.len())
+        );
+    }
+
+    // Write to file locally
+    if prune_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            prune_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+enum BookmarkRewriteUrlsError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error("Invalid regex (--from): {0}")]
+    RegexError(#[from] regex::Error),
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error("{0} - nothing rewritten (use --keep-going to rewrite the other urls)")]
+    ItemFailed(String),
+    #[error(transparent)]
+    BatchFailed(#[from] BatchFailedError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn bookmark_rewrite_urls(
+    rewrite_args: &RewriteUrlsArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkRewriteUrlsError> {
+    let push = rewrite_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkRewriteUrlsError::PushWithoutUrl);
+    }
+
+    let from_regex = if rewrite_args.regex {
+        Some(regex::Regex::new(&rewrite_args.from)?)
+    } else {
+        None
+    };
+    let mut report = BatchReport::default();
+    let rewrite = |href: &str| {
+        let rewritten = match &from_regex {
+            Some(re) => Some(re.replace_all(href, rewrite_args.to.as_str()).into_owned()),
+            None => href
+                .contains(rewrite_args.from.as_str())
+                .then(|| href.replace(rewrite_args.from.as_str(), rewrite_args.to.as_str())),
+        }?;
+        // Note: an url which would become invalid is kept as is (and reported)
+        if rewritten != href && Url::parse(&rewritten).is_err() {
+            report.failures.push(format!(
+                "Rewritten url is not a valid url: {} -> {}",
+                href, rewritten
+            ));
+            return None;
+        }
+        Some(rewritten)
+    };
+
+    // Read xbel file
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+
+    let rewritten = xbel.rewrite_urls(rewrite);
+    // Note: without --keep-going, fail before anything is written
+    if let (false, Some(failure)) = (rewrite_args.keep_going, report.failures.first()) {
+        return Err(BookmarkRewriteUrlsError::ItemFailed(failure.clone()));
+    }
+    report.succeeded = rewritten.len();
+    for (id, previous) in rewritten.iter() {
+        let href = id
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| xbel.get_bookmark(id))
+            .map(|b| b.href.as_str())
+            .unwrap_or_default();
+        if rewrite_args.dry_run {
+            println!("[Dry run] rewriting url: [{}] {} -> {}", id, previous, href);
+        } else {
+            info!("Rewritten url: [{}] {} -> {}", id, previous, href);
+        }
+    }
+    if rewrite_args.dry_run {
+        return Ok(report.finish(rewrite_args.keep_going)?);
+    }
+
+    if rewritten.is_empty() {
+        if report.failures.is_empty() {
+            println!("No url matching: {}", rewrite_args.from);
+        }
+        if !rewrite_args.write_args.normalize {
+            return Ok(report.finish(rewrite_args.keep_going)?);
+        }
+    } else {
+        println!(
+            "Rewritten {} {}",
+            rewritten.len(),
+            pluralize("url", rewritten.len())
+        );
+    }
+
+    // Write to file locally
+    if rewrite_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            rewrite_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(report.finish(rewrite_args.keep_going)?)
+}
+
+#[cfg(feature = "check-links")]
+#[derive(Error, Debug)]
+enum BookmarkCheckLinksError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+#[cfg(feature = "check-links")]
+fn bookmark_check_links(
+    check_args: &CheckLinksArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkCheckLinksError> {
+    let push = check_args.remove_dead && check_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkCheckLinksError::PushWithoutUrl);
+    }
+
+    // Read xbel file
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+
+    // Only http(s) urls can be checked
+    let bookmarks = xbel
+        .flatten()
+        .into_iter()
+        .map(|(_path, b)| b)
+        .filter(|b| Url::parse(&b.href).is_ok_and(|url| matches!(url.scheme(), "http" | "https")))
+        .collect::<Vec<_>>();
+    info!(
+        "Checking {} {}",
+        bookmarks.len(),
+        pluralize("link", bookmarks.len())
+    );
+    let urls = bookmarks
+        .iter()
+        .map(|b| b.href.as_str())
+        .collect::<Vec<_>>();
+    let statuses = web::check_links(
+        &urls,
+        check_args.concurrency,
+        std::time::Duration::from_secs(check_args.timeout),
+    );
+
+    let mut dead = vec![];
+    for (b, status) in bookmarks.iter().zip(statuses.iter()) {
+        let reason = match status {
+            web::LinkStatus::Alive(_) => continue,
+            web::LinkStatus::Status(code) => format!("http status {}", code),
+            web::LinkStatus::Error(e) => e.clone(),
+        };
+        println!("[{}] {} ({}): {}", b.id, b.title.text, b.href, reason);
+        dead.push(b.id.clone());
+    }
+    println!(
+        "Checked {} {}, {} dead",
+        bookmarks.len(),
+        pluralize("link", bookmarks.len()),
+        dead.len()
+    );
+
+    if !check_args.remove_dead || dead.is_empty() {
+        return Ok(());
+    }
+
+    let dead_ids: Vec<u64> = dead.iter().filter_map(|id| id.parse().ok()).collect();
+    let removed = remove_bookmarks(&mut xbel, &dead_ids, check_args.dry_run);
+    if check_args.dry_run {
+        return Ok(());
+    }
+    println!("Removed {} {}", removed, pluralize("bookmark", removed));
 
     // Write to file locally
-    atomic_write(bookmark_file_path.as_path(), xbel.to_string(), false)?;
+    if check_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            check_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+enum BookmarkUndoError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    RevertError(#[from] GitRevertError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+}
+
+fn bookmark_undo(
+    undo_args: &UndoArgs,
+    repo: &Repository,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkUndoError> {
+    let push = undo_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkUndoError::PushWithoutUrl);
+    }
+    if push {
+        // Check remote first (before the revert commit)
+        git_find_remote(repo, remote.name)?;
+    }
 
-    if rm_args.disable_push == Some(false) {
-        git_push(repo, bookmark_file_path_xbel.as_path())?;
+    let oid = git_revert_head(
+        repo,
+        undo_args.force,
+        undo_args.push_args.message.as_deref(),
+    )?;
+    println!("Reverted the last commit (revert commit: {})", oid);
+
+    if push {
+        git_push_head(repo, remote, auth)?;
     }
 
     Ok(())
@@ -500,7 +2122,13 @@ fn bookmark_rm(
 #[derive(Error, Debug)]
 enum BookmarkFindError {
     #[error(transparent)]
-    XbelReadError(#[from] XbelError),
+    XbelReadError(#[from] XbelFromFileError),
+    #[error(transparent)]
+    AtRevisionError(#[from] XbelAtRevisionError),
+    #[error("No folder or bookmark with id: {0}")]
+    IdNotFound(u64),
+    #[error("Invalid glob pattern: {0}")]
+    GlobError(#[from] globset::Error),
 }
 
 enum FindKind {
@@ -518,7 +2146,10 @@ enum FindWhere {
 fn bookmark_find(
     find_args: &FindArgs,
     repository_folder: PathBuf,
-) -> Result<(), BookmarkFindError> {
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    color: bool,
+) -> Result<usize, BookmarkFindError> {
     let find_kind = if find_args.folder {
         FindKind::Folder
     } else if find_args.bookmark {
@@ -536,35 +2167,73 @@ fn bookmark_find(
     };
 
     // Read xbel file
-    let bookmark_file_path_xbel = PathBuf::from("bookmarks.xbel");
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
     let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
-    let xbel = Xbel::try_from_file(&bookmark_file_path)?;
+    let xbel = match find_args.since.as_deref() {
+        Some(rev) => xbel_at_revision(repo, rev, bookmark_file)?,
+        None => xbel_from_file(&bookmark_file_path)?,
+    };
+
+    // Substring (or whole text with --glob) match
+    let glob = if find_args.glob {
+        Some(find_glob(find_args.find.as_str())?)
+    } else {
+        None
+    };
+    // Date filters (--added-before / --added-after): items without a (valid) date are excluded
+    // unless --include-undated
+    let date_filter_active = find_args.added_before.is_some() || find_args.added_after.is_some();
+    let match_date = |item: &XbelItem| {
+        let added = match item {
+            XbelItem::Bookmark(b) => b.added.as_deref().and_then(|a| Date::from_str(a).ok()),
+            _ => None,
+        };
+        match added {
+            Some(added) => {
+                find_args.added_before.map_or(true, |d| added < d)
+                    && find_args.added_after.map_or(true, |d| added >= d)
+            }
+            None => find_args.include_undated,
+        }
+    };
+
+    // Url filters (--host / --scheme)
+    let url_filter_active = find_args.host.is_some() || find_args.scheme.is_some();
+
+    // Tag filter (--tag): folders are excluded
+    let match_tag = |item: &XbelItem, tag: &str| match item {
+        XbelItem::Bookmark(b) => b.has_tag(tag),
+        _ => false,
+    };
 
-    let found_in_title = |item: &XbelItem, to_match: &str| item.get_title().text.contains(to_match);
-    let found_in_url = |item: &XbelItem, to_match: &str| {
-        item.get_url().unwrap_or(&"".to_string()).contains(to_match)
+    // --id: only the item with this id (other filters still apply)
+    let candidates: Vec<&XbelItem> = match find_args.id {
+        Some(id) => {
+            let (item_index, items) = xbel
+                .get_items(&XbelPath::Id(id))
+                .ok_or(BookmarkFindError::IdNotFound(id))?;
+            vec![&items[item_index]]
+        }
+        None => xbel.into_iter().collect(),
     };
-    let items: Vec<&XbelItem> = xbel
+
+    let items: Vec<&XbelItem> = candidates
         .into_iter()
+        .filter(|i| !date_filter_active || match_date(i))
+        .filter(|i| {
+            !url_filter_active
+                || find_url_match(i, find_args.host.as_deref(), find_args.scheme.as_deref())
+        })
+        .filter(|i| find_args.tag.as_ref().map_or(true, |tag| match_tag(i, tag)))
         .filter(|i| {
             let match_kind = match find_kind {
                 FindKind::Folder => matches!(i, XbelItem::Folder(_)),
                 FindKind::Bookmark => matches!(i, XbelItem::Bookmark(_)),
-                FindKind::All => true,
+                // Note: an alias has no title nor url
+                FindKind::All => !matches!(i, XbelItem::Alias { .. }),
             };
 
-            if !match_kind {
-                false
-            } else {
-                match find_where {
-                    FindWhere::Title => found_in_title(i, find_args.find.as_str()),
-                    FindWhere::Url => found_in_url(i, find_args.find.as_str()),
-                    FindWhere::All => {
-                        let to_find = find_args.find.as_str();
-                        found_in_title(i, to_find) || found_in_url(i, to_find)
-                    }
-                }
-            }
+            match_kind && find_text_match(i, find_args.find.as_str(), glob.as_ref(), &find_where)
         })
         .collect();
 
@@ -596,14 +2265,483 @@ fn bookmark_find(
         };
 
         println!("{}", msg);
-        for (idx, i) in items.iter().enumerate() {
-            println!("{}- {:?}", idx, i);
+        let limit = find_args.limit.unwrap_or(items.len());
+        for (idx, i) in items.iter().take(limit).enumerate() {
+            let folder_path = find_args
+                .show_path
+                .then(|| folder_path_of(&xbel, i))
+                .flatten();
+            let style = match i {
+                XbelItem::Folder(_) => FOLDER_STYLE,
+                XbelItem::Bookmark(_) | XbelItem::Alias { .. } => BOOKMARK_STYLE,
+            };
+            let item = paint(&format!("{:?}", i), style, color).into_owned();
+            match folder_path {
+                Some(folder_path) if !folder_path.is_empty() => {
+                    println!("{}- {} > {}", idx, folder_path, item)
+                }
+                _ => println!("{}- {}", idx, item),
+            }
+        }
+        if items.len() > limit {
+            println!("... and {} more", items.len() - limit);
+        }
+    }
+
+    Ok(items.len())
+}
+
+/// Glob pattern for find --glob: * does not match / (but ** does)
+fn find_glob(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+    Ok(GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()?
+        .compile_matcher())
+}
+
+/// Substring match (or whole text match with a glob) in the item title and/or url
+fn find_text_match(
+    item: &XbelItem,
+    to_find: &str,
+    glob: Option<&GlobMatcher>,
+    find_where: &FindWhere,
+) -> bool {
+    let is_match = |text: &str| match glob {
+        Some(glob) => glob.is_match(text),
+        None => text.contains(to_find),
+    };
+    let found_in_title = || is_match(&item.get_title().text);
+    let found_in_url = || {
+        let url = item.get_url().map_or("", String::as_str);
+        // Note: a glob must match the whole url, the scheme can be omitted
+        let url_without_scheme = url.split_once("://").map(|(_scheme, rest)| rest);
+        is_match(url) || (glob.is_some() && url_without_scheme.is_some_and(is_match))
+    };
+    match find_where {
+        FindWhere::Title => found_in_title(),
+        FindWhere::Url => found_in_url(),
+        FindWhere::All => found_in_title() || found_in_url(),
+    }
+}
+
+/// Url filters (find --host / --scheme): folders and bookmarks with an invalid url never match
+fn find_url_match(item: &XbelItem, host: Option<&str>, scheme: Option<&str>) -> bool {
+    let Some(url) = item.get_url().and_then(|u| Url::parse(u).ok()) else {
+        return false;
+    };
+    let match_host = host.map_or(true, |host| is_same_host(&url, host));
+    let match_scheme = scheme.map_or(true, |scheme| url.scheme() == scheme.to_lowercase());
+    match_host && match_scheme
+}
+
+/// Url host is the given host or a subdomain of it (e.g. gist.github.com for github.com)
+fn is_same_host(url: &Url, host: &str) -> bool {
+    let host = host.to_lowercase();
+    url.host_str().is_some_and(|h| {
+        h == host
+            || h.strip_suffix(host.as_str())
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+/// The folder path (escaped folder titles from root level) containing the given item
+///
+/// Returns an empty string if the item is at root level (None if not found)
+fn folder_path_of(xbel: &Xbel, item: &XbelItem) -> Option<String> {
+    let id = item.numeric_id()?;
+    let folders = xbel.find_path_of(id)?;
+    Some(
+        folders
+            .iter()
+            .map(|f| XbelPath::escape_title(&f.title.text))
+            .collect::<Vec<_>>()
+            .join(&XbelPath::SEPARATOR.to_string()),
+    )
+}
+
+fn bookmark_export(
+    export_args: &ExportArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+) -> anyhow::Result<()> {
+    let bookmark_file_path = repository_folder.join(bookmark_file);
+    let xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while parsing: {}", bookmark_file_path.display()))?;
+    let xbel = match export_args.under.as_ref().map(XbelPath::from) {
+        None => xbel,
+        Some(xbel_path) => xbel
+            .subtree(&xbel_path)
+            .ok_or_else(|| anyhow!("Cannot find anything in Xbel matching: {}", xbel_path))?,
+    };
+
+    match export_args.format {
+        ExportFormat::Json => {
+            let json_pretty = export_args
+                .json_args
+                .is_pretty(writes_to_terminal(export_args.output.as_deref()));
+            let mut output = output_writer(export_args.output.as_deref())?;
+            if json_pretty {
+                serde_json::to_writer_pretty(&mut output, &xbel)?;
+            } else {
+                serde_json::to_writer(&mut output, &xbel)?;
+            }
+            writeln!(output)?;
+            output.flush()?;
+        }
+        ExportFormat::Csv => {
+            let mut output = output_writer(export_args.output.as_deref())?;
+            write_csv(&xbel, export_args.include_folders, &mut output)?;
+            output.flush()?;
+        }
+        ExportFormat::Xbel => {
+            let options = WriteOptions {
+                highest_id_comment: !export_args.no_comment,
+                doctype: !export_args.no_doctype,
+                ..Default::default()
+            };
+            let mut output = output_writer(export_args.output.as_deref())?;
+            xbel.to_writer_with_options(&mut output, &options)?;
+            output.flush()?;
+        }
+        ExportFormat::Markdown => {
+            let mut output = output_writer(export_args.output.as_deref())?;
+            output.write_all(xbel.to_markdown().as_bytes())?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write bookmarks (and optionally folders) as csv rows: path,title,href,id
+fn write_csv<W: Write>(xbel: &Xbel, include_folders: bool, output: &mut W) -> std::io::Result<()> {
+    let mut write_row = |row: [&str; 4]| {
+        let row = row.map(csv_field);
+        writeln!(output, "{}", row.join(","))
+    };
+
+    write_row(["path", "title", "href", "id"])?;
+    if include_folders {
+        // Folder path stack (escaped folder titles)
+        let mut path: Vec<String> = vec![];
+        let separator = XbelPath::SEPARATOR.to_string();
+        for item in XbelNestingIterator::new(xbel) {
+            match item {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    write_row([&path.join(&separator), &f.title.text, "", &f.id])?;
+                    path.push(XbelPath::escape_title(&f.title.text));
+                }
+                XbelItemOrEnd::Item(XbelItem::Bookmark(b)) => {
+                    write_row([&path.join(&separator), &b.title.text, &b.href, &b.id])?;
+                }
+                XbelItemOrEnd::Item(XbelItem::Alias { .. }) => {}
+                XbelItemOrEnd::End { .. } => {
+                    path.pop();
+                }
+            }
+        }
+    } else {
+        for (path, b) in xbel.flatten() {
+            write_row([&path, &b.title.text, &b.href, &b.id])?;
+        }
+    }
+    Ok(())
+}
+
+/// Quote a csv field if needed (RFC 4180: fields with comma, quote or line break are quoted,
+/// quotes are doubled)
+fn csv_field(field: &str) -> Cow<str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[derive(Error, Debug)]
+enum BookmarkImportError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error("Error while reading: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Cannot parse json file: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error(transparent)]
+    XbelWriteError(#[from] XbelError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+}
+
+fn bookmark_import(
+    import_args: &ImportArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkImportError> {
+    let push = import_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkImportError::PushWithoutUrl);
+    }
+
+    let reader = std::io::BufReader::new(std::fs::File::open(&import_args.file)?);
+    let mut xbel: Xbel = match import_args.format {
+        ImportFormat::Json => serde_json::from_reader(reader)?,
+    };
+
+    // Write to file locally
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    info!(
+        "Importing {} into: {}",
+        import_args.file.display(),
+        bookmark_file_path.display()
+    );
+    if import_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            import_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Print all issues found in the bookmark file, returns the number of issues
+fn bookmark_validate(repository_folder: PathBuf, bookmark_file: &Path) -> anyhow::Result<usize> {
+    let bookmark_file_path = repository_folder.join(bookmark_file);
+    let xbel = xbel_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while parsing: {}", bookmark_file_path.display()))?;
+
+    let issues = xbel.validate();
+    if issues.is_empty() {
+        println!("No issues found");
+    } else {
+        println!(
+            "Found {} {}:",
+            issues.len(),
+            pluralize("issue", issues.len())
+        );
+        for issue in issues.iter() {
+            println!("- {}", issue);
+        }
+    }
+
+    Ok(issues.len())
+}
+
+/// Print the last commits (short id, date, author & message)
+fn bookmark_log(
+    log_args: &LogArgs,
+    repo: &Repository,
+    bookmark_file: &Path,
+) -> Result<(), git2::Error> {
+    let file = log_args.bookmark_file.then_some(bookmark_file);
+    let commits = git_log(repo, log_args.limit, file)?;
+    if commits.is_empty() {
+        println!("No commits");
+    }
+    for commit in commits.iter() {
+        println!(
+            "{} {} {} - {}",
+            commit.short_id,
+            commit.date(),
+            commit.author,
+            commit.summary
+        );
+    }
+    Ok(())
+}
+
+fn bookmark_diff(
+    diff_args: &DiffArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+) -> anyhow::Result<()> {
+    let bookmark_file_path = repository_folder.join(bookmark_file);
+    let xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while parsing: {}", bookmark_file_path.display()))?;
+    let other = Xbel::try_from_file(&diff_args.other)
+        .with_context(|| format!("Error while parsing: {}", diff_args.other.display()))?;
+
+    let changes = diff(&xbel, &other);
+    if changes.is_empty() {
+        println!("No differences");
+    } else {
+        for change in changes.iter() {
+            println!("{}", change);
         }
     }
 
     Ok(())
 }
 
+#[derive(Error, Debug)]
+enum BookmarkMergeError {
+    #[error("Error: please provide git repository url (or use --disable-push)")]
+    PushWithoutUrl,
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error(transparent)]
+    GitError(#[from] git2::Error),
+    #[error(transparent)]
+    WriteError(#[from] AtomicWriteError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+fn bookmark_merge(
+    merge_args: &MergeArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+    repo: Option<&Repository>,
+    remote: &GitRemote,
+    repository_url: Option<Url>,
+    auth: &GitAuth,
+) -> Result<(), BookmarkMergeError> {
+    let push = merge_args.push_args.should_push();
+    if push && repository_url.is_none() {
+        return Err(BookmarkMergeError::PushWithoutUrl);
+    }
+
+    // Read xbel files
+    let bookmark_file_path_xbel = bookmark_file.to_path_buf();
+    let bookmark_file_path = repository_folder.join(bookmark_file_path_xbel.as_path());
+    let mut xbel = Xbel::try_from_file(&bookmark_file_path)
+        .with_context(|| format!("Error while reading: {:?}", bookmark_file_path))?;
+    let other = Xbel::try_from_file(&merge_args.file)
+        .with_context(|| format!("Error while reading: {:?}", merge_args.file))?;
+
+    let mapping = xbel.merge(other, merge_args.fuse);
+    info!(
+        "Merged {} {} from: {}",
+        mapping.len(),
+        pluralize("item", mapping.len()),
+        merge_args.file.display()
+    );
+
+    // Write to file locally
+    if merge_args.write_args.normalize {
+        info!("Normalized {} title(s)", xbel.normalize_titles());
+    }
+    atomic_write(
+        bookmark_file_path.as_path(),
+        xbel_content(&xbel, &bookmark_file_path)?,
+        false,
+    )?;
+
+    // Note: no git repository (and no push) with --xbel
+    if let (true, Some(repo)) = (push, repo) {
+        git_push(
+            repo,
+            bookmark_file_path_xbel.as_path(),
+            merge_args.push_args.message.as_deref(),
+            remote,
+            auth,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+enum BookmarkOpenError {
+    #[error(transparent)]
+    XbelReadError(#[from] XbelError),
+    #[error("Cannot find anything in Xbel matching: {0}")]
+    XbelPathNotFound(XbelPath),
+    #[error("Item found with id: {0} but it is not a bookmark")]
+    NotaBookmark(String),
+    #[error("Invalid url: {0} ({1})")]
+    InvalidUrl(String, String),
+    #[error("Url scheme: {1} is not opened by default (use --allow-any-scheme): {0}")]
+    SchemeNotAllowed(String, String),
+    #[error("Cannot open url: {0}")]
+    OpenError(#[from] std::io::Error),
+    #[error("Cannot open url: {0} (exit status: {1})")]
+    OpenStatusError(String, std::process::ExitStatus),
+}
+
+fn bookmark_open(
+    open_args: &OpenArgs,
+    repository_folder: PathBuf,
+    bookmark_file: &Path,
+) -> Result<(), BookmarkOpenError> {
+    // Read xbel file
+    let bookmark_file_path = repository_folder.join(bookmark_file);
+    let xbel = Xbel::try_from_file(&bookmark_file_path)?;
+
+    let xbel_path = XbelPath::Id(open_args.id);
+    let (item_index, items) = xbel
+        .get_items(&xbel_path)
+        .ok_or(BookmarkOpenError::XbelPathNotFound(xbel_path.clone()))?;
+
+    let url = match &items[item_index] {
+        XbelItem::Bookmark(b) => b.href.as_str(),
+        item => return Err(BookmarkOpenError::NotaBookmark(item.get_id().clone())),
+    };
+
+    check_open_url(url, open_args.allow_any_scheme)?;
+    info!("Opening url: {}", url);
+    let status = open_command(url).status()?;
+    if !status.success() {
+        return Err(BookmarkOpenError::OpenStatusError(url.to_string(), status));
+    }
+
+    Ok(())
+}
+
+/// Url schemes opened without --allow-any-scheme
+const OPEN_SCHEMES_ALLOWED: [&str; 3] = ["http", "https", "file"];
+
+/// Check that a bookmark url is valid (see `validate_url`) and safe to open
+fn check_open_url(url: &str, allow_any_scheme: bool) -> Result<(), BookmarkOpenError> {
+    validate_url(url).map_err(|e| BookmarkOpenError::InvalidUrl(url.to_string(), e))?;
+    let url_ = Url::parse(url)
+        .map_err(|e| BookmarkOpenError::InvalidUrl(url.to_string(), e.to_string()))?;
+    if !allow_any_scheme && !OPEN_SCHEMES_ALLOWED.contains(&url_.scheme()) {
+        return Err(BookmarkOpenError::SchemeNotAllowed(
+            url.to_string(),
+            url_.scheme().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Build the command used to open an url with the platform default application
+fn open_command(url: &str) -> std::process::Command {
+    let mut cmd = if cfg!(target_os = "windows") {
+        // Note: no shell (cmd /C start) here, the url would be interpreted by cmd.exe (e.g. &)
+        let mut cmd = std::process::Command::new("rundll32");
+        cmd.arg("url.dll,FileProtocolHandler");
+        cmd
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(url);
+    cmd
+}
+
 fn pluralize(s: &str, count: usize) -> Cow<'_, str> {
     match count {
         0 | 1 => Cow::Borrowed(s),
@@ -611,38 +2749,75 @@ fn pluralize(s: &str, count: usize) -> Cow<'_, str> {
     }
 }
 
+#[derive(Error, Debug)]
+#[error("{} {} failed (see summary above)", .0, pluralize("item", *.0))]
+struct BatchFailedError(usize);
+
+/// Items processed by a command acting on multiple items (see --keep-going)
+#[derive(Debug, Default)]
+struct BatchReport {
+    succeeded: usize,
+    /// Items skipped because of an error (item & reason)
+    failures: Vec<String>,
+}
+
+impl BatchReport {
+    /// Print the summary (only with --keep-going), error if any item failed
+    ///
+    /// Note: without --keep-going, rm --match and rewrite-urls fail on the first failed item
+    ///       (before writing anything)
+    fn finish(&self, keep_going: bool) -> Result<(), BatchFailedError> {
+        if !keep_going {
+            return Ok(());
+        }
+        println!(
+            "{} succeeded, {} failed",
+            self.succeeded,
+            self.failures.len()
+        );
+        for failure in self.failures.iter() {
+            println!("  {}", failure);
+        }
+        match self.failures.len() {
+            0 => Ok(()),
+            failed => Err(BatchFailedError(failed)),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 enum AtomicWriteError {
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
     PathPersist(#[from] PathPersistError),
-    #[error("Cannot determine local cache folder for temp files")]
-    TmpFolderError,
 }
 
-fn atomic_write(
+/// Xbel file content (gzip compressed for a .gz file)
+fn xbel_content(xbel: &Xbel, file_path: &Path) -> Result<Vec<u8>, XbelError> {
+    if Xbel::is_gzip_path(file_path) {
+        let mut content = vec![];
+        xbel.to_gzip_writer(&mut content)?;
+        Ok(content)
+    } else {
+        Ok(xbel.try_to_string()?.into_bytes())
+    }
+}
+
+fn atomic_write<C: AsRef<[u8]>>(
     file_path: &Path,
-    content: String,
+    content: C,
     no_clobber: bool,
 ) -> Result<(), AtomicWriteError> {
     let mut tmp_file = if cfg!(target_os = "linux") {
         // Avoid linux error like:
         // failed to persist temporary file: Invalid cross-device link
-        NamedTempFile::new_in(
-            ProjectDirs::from(
-                FLOCCUS_CLI_QUALIFIER,
-                FLOCCUS_CLI_ORGANIZATION,
-                FLOCCUS_CLI_APPLICATION,
-            )
-            .ok_or(AtomicWriteError::TmpFolderError)?
-            .cache_dir(),
-        )
+        NamedTempFile::new_in(cache_dir())
     } else {
         NamedTempFile::new()
     }?;
 
-    write!(tmp_file, "{}", content)?;
+    tmp_file.write_all(content.as_ref())?;
     let path = tmp_file.into_temp_path();
 
     if no_clobber {
@@ -652,3 +2827,371 @@ fn atomic_write(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_find_glob() {
+        let glob = find_glob("github.com/*/issues").unwrap();
+        assert!(glob.is_match("github.com/me/issues"));
+        assert!(!glob.is_match("github.com/me/repo/issues"));
+        assert!(!glob.is_match("https://github.com/me/issues"));
+        assert!(find_glob("github.com/**/issues")
+            .unwrap()
+            .is_match("github.com/me/repo/issues"));
+        assert!(find_glob("Bank [12]").unwrap().is_match("Bank 2"));
+        assert!(find_glob("{Bank,Shop} ?").unwrap().is_match("Shop 1"));
+        assert!(find_glob("a\\*").unwrap().is_match("a*"));
+        assert!(!find_glob("a\\*").unwrap().is_match("ab"));
+        assert!(find_glob("[a-").is_err());
+    }
+
+    #[test]
+    fn test_is_same_repository_url() {
+        let url = Url::parse("https://github_pat_TOKEN@github.com/me/bookmarks.git").unwrap();
+        assert!(is_same_repository_url(
+            &url,
+            "https://github.com/me/bookmarks.git"
+        ));
+        assert!(is_same_repository_url(
+            &url,
+            "https://GitHub.com/me/bookmarks/"
+        ));
+        assert!(!is_same_repository_url(
+            &url,
+            "https://github.com/me/other.git"
+        ));
+        assert!(!is_same_repository_url(
+            &url,
+            "ssh://git@github.com/me/bookmarks.git"
+        ));
+        assert!(!is_same_repository_url(
+            &url,
+            "git@github.com:me/bookmarks.git"
+        ));
+        assert_eq!(repository_url_key(&url), "https://github.com/me/bookmarks");
+    }
+
+    #[test]
+    fn test_setup_repo_not_a_repository() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("notes.txt"), "not a bookmark").unwrap();
+        let cli = Cli::parse_from([
+            "floccus_cli",
+            "-r",
+            tmp_dir.path().to_str().unwrap(),
+            "print",
+        ]);
+        let Err(err) = setup_repo(&cli, tmp_dir.path()) else {
+            panic!("A folder which is not a git repository should be rejected");
+        };
+        assert!(
+            err.to_string().contains("is not a git repository"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_add_overwrite_position() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let xbel_path = tmp_dir.path().join("bookmarks.xbel");
+        let add = |args: &[&str]| {
+            let cli = Cli::parse_from(
+                [
+                    &["floccus_cli", "--xbel", xbel_path.to_str().unwrap(), "add"],
+                    args,
+                    &["--id", "3", "--overwrite", "--disable-push"],
+                ]
+                .concat(),
+            );
+            let Commands::Add(add_args) = &cli.command else {
+                panic!("Expected add command");
+            };
+            bookmark_add(
+                add_args,
+                tmp_dir.path().to_path_buf(),
+                Path::new("bookmarks.xbel"),
+                None,
+                &git_remote(&cli),
+                None,
+                &git_auth(&cli),
+            )
+            .unwrap();
+            let xbel = Xbel::try_from_file(&xbel_path).unwrap();
+            let (item_index, items) = xbel.get_items(&XbelPath::Id(3)).unwrap();
+            let XbelItem::Bookmark(b) = &items[item_index] else {
+                panic!("Expected a bookmark");
+            };
+            (
+                folder_path_of(&xbel, &items[item_index]).unwrap_or_default(),
+                item_index,
+                b.href.clone(),
+            )
+        };
+
+        // Replaced in place (no --under or --under relative to the replaced bookmark)
+        for under in [&[][..], &["-u", "after=3"], &["-u", "before=3"]] {
+            std::fs::copy("../ressources/bookmarks_bank_v1.xbel", &xbel_path).unwrap();
+            let args = [&["-b", "https://example.com/", "-t", "Example"], under].concat();
+            assert_eq!(
+                add(&args),
+                (
+                    "admin/bank".to_string(),
+                    0,
+                    "https://example.com/".to_string()
+                )
+            );
+        }
+
+        // Removed then added under the given folder
+        std::fs::copy("../ressources/bookmarks_bank_v1.xbel", &xbel_path).unwrap();
+        let (folder_path, item_index, _href) = add(&[
+            "-b",
+            "https://example.com/",
+            "-t",
+            "Example",
+            "-u",
+            "prepend=root",
+        ]);
+        assert_eq!((folder_path.as_str(), item_index), ("", 0));
+    }
+
+    #[test]
+    fn test_move_root_item() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let xbel_path = tmp_dir.path().join("bookmarks.xbel");
+        std::fs::copy("../ressources/bookmarks_bank_v1.xbel", &xbel_path).unwrap();
+        let cli = Cli::parse_from([
+            "floccus_cli",
+            "--xbel",
+            xbel_path.to_str().unwrap(),
+            "move",
+            "-i",
+            "1",
+            "-u",
+            "2",
+            "--disable-push",
+        ]);
+        let Commands::Move(mut move_args) = cli.command.clone() else {
+            panic!("Expected move command");
+        };
+        // Note: rejected by the cli parser, but bookmark_move must not move the first item
+        move_args.item = Under::Root(Placement::InFolderAppend);
+        let res = bookmark_move(
+            &move_args,
+            tmp_dir.path().to_path_buf(),
+            Path::new("bookmarks.xbel"),
+            None,
+            &git_remote(&cli),
+            None,
+            &git_auth(&cli),
+        );
+        assert!(matches!(res, Err(BookmarkMoveError::RootNotAnItem)));
+        assert_eq!(
+            std::fs::read_to_string(&xbel_path).unwrap(),
+            std::fs::read_to_string("../ressources/bookmarks_bank_v1.xbel").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_copy_root_item() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let xbel_path = tmp_dir.path().join("bookmarks.xbel");
+        std::fs::copy("../ressources/bookmarks_bank_v1.xbel", &xbel_path).unwrap();
+        let cli = Cli::parse_from([
+            "floccus_cli",
+            "--xbel",
+            xbel_path.to_str().unwrap(),
+            "copy",
+            "-i",
+            "1",
+            "--disable-push",
+        ]);
+        let Commands::Copy(mut copy_args) = cli.command.clone() else {
+            panic!("Expected copy command");
+        };
+        // Note: rejected by the cli parser, but bookmark_copy must not copy the first item
+        copy_args.item = Under::Root(Placement::InFolderAppend);
+        let res = bookmark_copy(
+            &copy_args,
+            tmp_dir.path().to_path_buf(),
+            Path::new("bookmarks.xbel"),
+            None,
+            &git_remote(&cli),
+            None,
+            &git_auth(&cli),
+        );
+        assert!(matches!(res, Err(BookmarkCopyError::RootNotAnItem)));
+        assert_eq!(
+            std::fs::read_to_string(&xbel_path).unwrap(),
+            std::fs::read_to_string("../ressources/bookmarks_bank_v1.xbel").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_check_open_url() {
+        assert!(check_open_url("https://example.com/?a=1&b=2", false).is_ok());
+        assert!(check_open_url("file:///home/me/notes.html", false).is_ok());
+        assert!(matches!(
+            check_open_url("obsidian://open?vault=notes", false),
+            Err(BookmarkOpenError::SchemeNotAllowed(_, _))
+        ));
+        assert!(check_open_url("obsidian://open?vault=notes", true).is_ok());
+        assert!(matches!(
+            check_open_url("javascript:alert(1)", true),
+            Err(BookmarkOpenError::InvalidUrl(_, _))
+        ));
+        assert!(matches!(
+            check_open_url("not an url", false),
+            Err(BookmarkOpenError::InvalidUrl(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_init_config() {
+        let init_config_ = |args: &[&str]| {
+            let cli = Cli::parse_from([&["floccus_cli"], args, &["init"]].concat());
+            init_config(&cli)
+        };
+
+        let config = init_config_(&["-g", "https://github.com/me/bookmarks.git", "-t", "TOKEN"])
+            .unwrap()
+            .to_string();
+        assert!(config.contains(r#"repository_token = "TOKEN""#));
+        assert!(!config.contains("repository_ssh_key"));
+        assert!(matches!(
+            init_config_(&["-g", "https://github.com/me/bookmarks.git"]),
+            Err(InitError::TokenNotProvided)
+        ));
+
+        let ssh_key = tempfile::NamedTempFile::new().unwrap();
+        let ssh_key = ssh_key.path().to_str().unwrap();
+        let ssh_url = "ssh://git@github.com/me/bookmarks.git";
+        let config = init_config_(&["-g", ssh_url, "-s", ssh_key, "-t", "TOKEN"])
+            .unwrap()
+            .to_string();
+        assert!(config.contains(&format!(r#"repository_ssh_key = "{}""#, ssh_key)));
+        assert!(!config.contains("repository_token"));
+        assert!(matches!(
+            init_config_(&["-g", ssh_url, "-s", "/nonexistent/id_ed25519"]),
+            Err(InitError::SshKeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_repository_name_from_url() {
+        let name = |url: &str| repository_name_from_url(&Url::parse(url).unwrap());
+        assert_eq!(
+            name("https://github.com/me/my-bookmarks.git"),
+            Some("my-bookmarks".to_string())
+        );
+        assert_eq!(
+            name("ssh://git@github.com/me/my-bookmarks/"),
+            Some("my-bookmarks".to_string())
+        );
+        assert_eq!(name("https://github.com/"), None);
+
+        let cli = Cli::parse_from([
+            "floccus_cli",
+            "-g",
+            "https://github.com/me/my-bookmarks.git",
+            "-t",
+            "TOKEN",
+            "init",
+        ]);
+        let config = init_config(&cli).unwrap().to_string();
+        assert!(config.contains(r#"repository_name = "my-bookmarks""#));
+        let cli = Cli::parse_from([
+            "floccus_cli",
+            "-g",
+            "https://github.com/me/my-bookmarks.git",
+            "-t",
+            "TOKEN",
+            "-n",
+            "work",
+            "init",
+        ]);
+        let config = init_config(&cli).unwrap().to_string();
+        assert!(config.contains(r#"repository_name = "work""#));
+    }
+
+    #[test]
+    fn test_parse_bookmark_list() {
+        let content = "# My list\n\nhttps://www.rust-lang.org\tRust\nhttps://crates.io\nnot an url\n  https://docs.rs\t  \n";
+        let (bookmarks, malformed) = parse_bookmark_list(content, false);
+
+        assert_eq!(
+            bookmarks,
+            vec![
+                ("https://www.rust-lang.org".to_string(), "Rust".to_string()),
+                (
+                    "https://crates.io".to_string(),
+                    "https://crates.io".to_string()
+                ),
+                ("https://docs.rs".to_string(), "https://docs.rs".to_string()),
+            ]
+        );
+        assert_eq!(malformed, vec![(5, "not an url".to_string())]);
+
+        let content = "javascript:alert(1)\tBookmarklet\nnot_an_url\n";
+        let (bookmarks, malformed) = parse_bookmark_list(content, false);
+        assert!(bookmarks.is_empty());
+        assert_eq!(malformed.len(), 2);
+        let (bookmarks, malformed) = parse_bookmark_list(content, true);
+        assert_eq!(bookmarks.len(), 2);
+        assert!(malformed.is_empty());
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(csv_field("Bank 1"), "Bank 1");
+        assert_eq!(csv_field("Bank 1, 2"), "\"Bank 1, 2\"");
+        assert_eq!(csv_field("Bank \"1\""), "\"Bank \"\"1\"\"\"");
+
+        let xbel = Xbel::new(Some(vec![XbelItem::new_folder(
+            "1",
+            "admin, news",
+            Some(vec![XbelItem::new_bookmark("2", "https://a.b/", "A")]),
+        )]));
+        let mut output = vec![];
+        write_csv(&xbel, false, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "path,title,href,id\n\"admin, news\",A,https://a.b/,2\n"
+        );
+        let mut output = vec![];
+        write_csv(&xbel, true, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "path,title,href,id\n,\"admin, news\",,1\n\"admin, news\",A,https://a.b/,2\n"
+        );
+    }
+
+    #[test]
+    fn test_is_same_host() {
+        let url = Url::parse("https://gist.github.com/foo").unwrap();
+        assert!(is_same_host(&url, "github.com"));
+        assert!(is_same_host(&url, "Gist.GitHub.com"));
+        assert!(!is_same_host(&url, "hub.com"));
+        let url = Url::parse("https://notgithub.example/foo").unwrap();
+        assert!(!is_same_host(&url, "github.com"));
+        let url = Url::parse("file:///tmp/foo.html").unwrap();
+        assert!(!is_same_host(&url, "github.com"));
+    }
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://www.rust-lang.org").is_ok());
+        assert!(validate_url("ftp://ftp.example.com/file.txt").is_ok());
+        assert!(validate_url("file:///home/user/doc.html").is_ok());
+        assert!(validate_url("not a url").is_err());
+        assert!(validate_url("www.rust-lang.org").is_err());
+        assert!(validate_url("javascript:alert(1)").is_err());
+        assert!(validate_url("file:///home/me/My%20Docs/x%2Fy.pdf").is_ok());
+        assert!(validate_url("obsidian://open?vault=notes&file=a%20b").is_ok());
+    }
+}
@@ -0,0 +1,3 @@
+mod print_format;
+
+pub use print_format::{paint, print_items, PrintOptions, BOOKMARK_STYLE, FOLDER_STYLE};
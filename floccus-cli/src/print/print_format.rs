@@ -0,0 +1,505 @@
+// std
+use std::borrow::Cow;
+use std::io::Write;
+// third-party
+use anstyle::{AnsiColor, Style};
+use floccus_xbel::{Folder, XbelItem, XbelItemOrEnd, XbelNestingIterator};
+// internal
+use crate::cli::PrintFormat;
+
+const FOLDER_EMOTICON: &str = "\u{1F4C1}";
+const FOLDER_LINK1: &str = "\u{1F517}";
+const ALIAS_ARROW: &str = "\u{21AA}";
+
+pub const FOLDER_STYLE: Style = Style::new()
+    .fg_color(Some(anstyle::Color::Ansi(AnsiColor::Blue)))
+    .bold();
+pub const BOOKMARK_STYLE: Style =
+    Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Green)));
+const URL_STYLE: Style = Style::new().fg_color(Some(anstyle::Color::Ansi(AnsiColor::Cyan)));
+
+/// Print options
+#[derive(Debug, Clone, Default)]
+pub struct PrintOptions {
+    /// Only print items up to this depth (0: only top level items, None: no limit)
+    pub max_depth: Option<usize>,
+    /// Json format: indented (or single line) output
+    pub json_pretty: bool,
+    /// Plain & tree formats: colored folders, bookmarks & urls
+    pub color: bool,
+}
+
+/// Text with the given style (or unchanged if color is disabled)
+pub fn paint(text: &str, style: Style, color: bool) -> Cow<'_, str> {
+    if color {
+        Cow::Owned(format!(
+            "{}{}{}",
+            style.render(),
+            text,
+            style.render_reset()
+        ))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Print items (and nested items) using the given format & options
+pub fn print_items<W: Write>(
+    items: XbelNestingIterator,
+    format: PrintFormat,
+    options: &PrintOptions,
+    output: &mut W,
+) -> std::io::Result<()> {
+    let renderer = Renderer {
+        events: VisibleEvents::new(items, options.max_depth),
+        max_depth: options.max_depth,
+        json_pretty: options.json_pretty,
+        color: options.color,
+    };
+    match format {
+        PrintFormat::Plain => renderer.plain(output),
+        PrintFormat::Tree => renderer.tree(output),
+        PrintFormat::Json => renderer.json(output),
+        PrintFormat::Html => renderer.html(output),
+    }
+}
+
+/// Items & folder ends (with their depth, 0: top level) up to a max depth
+struct VisibleEvents<'a> {
+    nesting_iter: XbelNestingIterator<'a>,
+    max_depth: Option<usize>,
+    depth: usize,
+}
+
+impl<'a> VisibleEvents<'a> {
+    fn new(nesting_iter: XbelNestingIterator<'a>, max_depth: Option<usize>) -> Self {
+        Self {
+            nesting_iter,
+            max_depth,
+            depth: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for VisibleEvents<'a> {
+    type Item = (usize, XbelItemOrEnd<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = self.nesting_iter.next()?;
+            let depth = match event {
+                XbelItemOrEnd::Item(XbelItem::Folder(_)) => {
+                    self.depth += 1;
+                    self.depth - 1
+                }
+                XbelItemOrEnd::Item(XbelItem::Bookmark(_) | XbelItem::Alias { .. }) => self.depth,
+                XbelItemOrEnd::End { .. } => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.depth
+                }
+            };
+            if self.max_depth.map_or(true, |max_depth| depth <= max_depth) {
+                return Some((depth, event));
+            }
+        }
+    }
+}
+
+struct Renderer<'a> {
+    events: VisibleEvents<'a>,
+    max_depth: Option<usize>,
+    json_pretty: bool,
+    color: bool,
+}
+
+impl Renderer<'_> {
+    /// Indented items (one line per folder, two lines per bookmark)
+    fn plain<W: Write>(self, output: &mut W) -> std::io::Result<()> {
+        const INDENTER: fn(usize) -> String = |indent_spaces| " ".repeat(indent_spaces);
+
+        for (depth, event) in self.events {
+            match event {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    writeln!(
+                        output,
+                        "{}[{FOLDER_EMOTICON} {}] {}{}",
+                        INDENTER(depth * 2),
+                        f.id,
+                        paint(&f.title.text, FOLDER_STYLE, self.color),
+                        folded(self.max_depth, depth, f)
+                    )?;
+                }
+                XbelItemOrEnd::Item(XbelItem::Bookmark(b)) => {
+                    let indent = INDENTER(depth * 2);
+                    writeln!(
+                        output,
+                        "{}[{FOLDER_LINK1} {}] {}",
+                        indent,
+                        b.id,
+                        paint(&b.title.text, BOOKMARK_STYLE, self.color)
+                    )?;
+                    writeln!(
+                        output,
+                        "{}- {}",
+                        indent,
+                        paint(&b.href, URL_STYLE, self.color)
+                    )?;
+                }
+                XbelItemOrEnd::Item(XbelItem::Alias { reference }) => {
+                    writeln!(
+                        output,
+                        "{}[{ALIAS_ARROW} {}] alias",
+                        INDENTER(depth * 2),
+                        reference
+                    )?;
+                }
+                XbelItemOrEnd::End { .. } => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Items with tree connectors (as the tree command)
+    fn tree<W: Write>(self, output: &mut W) -> std::io::Result<()> {
+        let events: Vec<_> = self.events.collect();
+
+        // Is the item the last one of its folder?
+        // Note: last item index (if any) of each folder currently open (+ top level)
+        let mut last_items: Vec<Option<usize>> = vec![None];
+        let mut is_last = vec![true; events.len()];
+        for (index, (_, event)) in events.iter().enumerate() {
+            match event {
+                XbelItemOrEnd::Item(item) => {
+                    if let Some(Some(previous)) = last_items.last() {
+                        is_last[*previous] = false;
+                    }
+                    if let Some(last_item) = last_items.last_mut() {
+                        *last_item = Some(index);
+                    }
+                    if let XbelItem::Folder(_) = item {
+                        last_items.push(None);
+                    }
+                }
+                XbelItemOrEnd::End { .. } => {
+                    last_items.pop();
+                }
+            }
+        }
+
+        // Prefix for the children of each folder currently open
+        let mut prefixes: Vec<String> = vec!["".to_string()];
+        for ((depth, event), is_last) in events.iter().zip(is_last) {
+            let prefix = prefixes.last().cloned().unwrap_or_default();
+            let connector = if is_last { "└── " } else { "├── " };
+            match event {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    writeln!(
+                        output,
+                        "{}{}{FOLDER_EMOTICON} [{}] {}{}",
+                        prefix,
+                        connector,
+                        f.id,
+                        paint(&f.title.text, FOLDER_STYLE, self.color),
+                        folded(self.max_depth, *depth, f)
+                    )?;
+                    let child_prefix = if is_last { "    " } else { "│   " };
+                    prefixes.push(format!("{}{}", prefix, child_prefix));
+                }
+                XbelItemOrEnd::Item(XbelItem::Bookmark(b)) => {
+                    writeln!(
+                        output,
+                        "{}{}{FOLDER_LINK1} [{}] {} ({})",
+                        prefix,
+                        connector,
+                        b.id,
+                        paint(&b.title.text, BOOKMARK_STYLE, self.color),
+                        paint(&b.href, URL_STYLE, self.color)
+                    )?;
+                }
+                XbelItemOrEnd::Item(XbelItem::Alias { reference }) => {
+                    writeln!(
+                        output,
+                        "{}{}{ALIAS_ARROW} [{}] alias",
+                        prefix, connector, reference
+                    )?;
+                }
+                XbelItemOrEnd::End { .. } => {
+                    prefixes.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Json array of items (using the floccus-xbel serde field names, as export --format json)
+    fn json<W: Write>(self, output: &mut W) -> std::io::Result<()> {
+        // Rebuild the visible items: children of the folders currently open (+ top level)
+        let mut folders: Vec<Folder> = vec![];
+        let mut items: Vec<Vec<XbelItem>> = vec![vec![]];
+        for (_, event) in self.events {
+            match event {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    folders.push(Folder::new(&f.id, &f.title.text, None));
+                    items.push(vec![]);
+                }
+                XbelItemOrEnd::Item(item) => {
+                    if let Some(children) = items.last_mut() {
+                        children.push(item.clone());
+                    }
+                }
+                XbelItemOrEnd::End { .. } => {
+                    if let (Some(mut folder), Some(children)) = (folders.pop(), items.pop()) {
+                        folder.items = children;
+                        if let Some(parent_children) = items.last_mut() {
+                            parent_children.push(XbelItem::Folder(folder));
+                        }
+                    }
+                }
+            }
+        }
+
+        let items = items.pop().unwrap_or_default();
+        if self.json_pretty {
+            serde_json::to_writer_pretty(&mut *output, &items)?;
+        } else {
+            serde_json::to_writer(&mut *output, &items)?;
+        }
+        writeln!(output)
+    }
+
+    /// Netscape bookmark file (html), as exported / imported by web browsers
+    fn html<W: Write>(self, output: &mut W) -> std::io::Result<()> {
+        const INDENTER: fn(usize) -> String = |depth| "    ".repeat(depth);
+
+        writeln!(output, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+        writeln!(
+            output,
+            "<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">"
+        )?;
+        writeln!(output, "<TITLE>Bookmarks</TITLE>")?;
+        writeln!(output, "<H1>Bookmarks</H1>")?;
+        writeln!(output, "<DL><p>")?;
+        for (depth, event) in self.events {
+            let indent = INDENTER(depth + 1);
+            match event {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    writeln!(
+                        output,
+                        "{}<DT><H3>{}</H3>",
+                        indent,
+                        html_escape(&f.title.text)
+                    )?;
+                    writeln!(output, "{}<DL><p>", indent)?;
+                }
+                XbelItemOrEnd::Item(XbelItem::Bookmark(b)) => {
+                    writeln!(
+                        output,
+                        "{}<DT><A HREF=\"{}\">{}</A>",
+                        indent,
+                        html_escape(&b.href),
+                        html_escape(&b.title.text)
+                    )?;
+                }
+                // Note: no alias in the Netscape bookmark file format
+                XbelItemOrEnd::Item(XbelItem::Alias { .. }) => {}
+                XbelItemOrEnd::End { .. } => {
+                    writeln!(output, "{}</DL><p>", indent)?;
+                }
+            }
+        }
+        writeln!(output, "</DL><p>")
+    }
+}
+
+/// Folder content is hidden (depth limit): the number of children (as " [+N]")
+fn folded(max_depth: Option<usize>, depth: usize, folder: &Folder) -> String {
+    if max_depth == Some(depth) && !folder.is_empty() {
+        format!(" [+{}]", folder.child_count())
+    } else {
+        "".to_string()
+    }
+}
+
+/// Escape html special characters (text & attribute values)
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use floccus_xbel::Xbel;
+
+    fn xbel_bank_items() -> Vec<XbelItem> {
+        vec![
+            XbelItem::new_folder(
+                "1",
+                "admin",
+                Some(vec![
+                    XbelItem::new_folder(
+                        "2",
+                        "bank",
+                        Some(vec![XbelItem::new_bookmark(
+                            "3",
+                            "https://www.bank1.com/",
+                            "Bank 1",
+                        )]),
+                    ),
+                    XbelItem::new_bookmark("4", "https://www.bank2.com/?a=1&b=2", "Bank <2>"),
+                ]),
+            ),
+            XbelItem::new_bookmark("5", "https://example.com/", "Example"),
+        ]
+    }
+
+    fn xbel_bank() -> Xbel {
+        Xbel::new(Some(xbel_bank_items()))
+    }
+
+    fn print_to_string(xbel: &Xbel, format: PrintFormat, max_depth: Option<usize>) -> String {
+        let mut output = vec![];
+        let options = PrintOptions {
+            max_depth,
+            json_pretty: true,
+            color: false,
+        };
+        print_items(
+            XbelNestingIterator::new(xbel),
+            format,
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_print_plain() {
+        let xbel = xbel_bank();
+        assert_eq!(
+            print_to_string(&xbel, PrintFormat::Plain, Some(0)),
+            "[\u{1F4C1} 1] admin [+2]\n[\u{1F517} 5] Example\n- https://example.com/\n"
+        );
+    }
+
+    #[test]
+    fn test_print_color() {
+        let xbel = xbel_bank();
+        let options = PrintOptions {
+            max_depth: Some(0),
+            json_pretty: false,
+            color: true,
+        };
+        let mut output = vec![];
+        print_items(
+            XbelNestingIterator::new(&xbel),
+            PrintFormat::Plain,
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        let expected = format!(
+            "[\u{1F4C1} 1] {}admin{} [+2]\n[\u{1F517} 5] {}Example{}\n- {}https://example.com/{}\n",
+            FOLDER_STYLE.render(),
+            FOLDER_STYLE.render_reset(),
+            BOOKMARK_STYLE.render(),
+            BOOKMARK_STYLE.render_reset(),
+            URL_STYLE.render(),
+            URL_STYLE.render_reset(),
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+
+        // Json is never colored
+        let mut output = vec![];
+        print_items(
+            XbelNestingIterator::new(&xbel),
+            PrintFormat::Json,
+            &options,
+            &mut output,
+        )
+        .unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains('\x1b'));
+    }
+
+    #[test]
+    fn test_print_tree() {
+        let xbel = xbel_bank();
+        let expected = [
+            "├── \u{1F4C1} [1] admin",
+            "│   ├── \u{1F4C1} [2] bank",
+            "│   │   └── \u{1F517} [3] Bank 1 (https://www.bank1.com/)",
+            "│   └── \u{1F517} [4] Bank <2> (https://www.bank2.com/?a=1&b=2)",
+            "└── \u{1F517} [5] Example (https://example.com/)",
+            "",
+        ];
+        assert_eq!(
+            print_to_string(&xbel, PrintFormat::Tree, None),
+            expected.join("\n")
+        );
+
+        // Folded folder
+        let expected = [
+            "├── \u{1F4C1} [1] admin",
+            "│   ├── \u{1F4C1} [2] bank [+1]",
+            "│   └── \u{1F517} [4] Bank <2> (https://www.bank2.com/?a=1&b=2)",
+            "└── \u{1F517} [5] Example (https://example.com/)",
+            "",
+        ];
+        assert_eq!(
+            print_to_string(&xbel, PrintFormat::Tree, Some(1)),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_print_json() {
+        let xbel = xbel_bank();
+        let json = print_to_string(&xbel, PrintFormat::Json, None);
+        let items: Vec<XbelItem> = serde_json::from_str(&json).unwrap();
+        assert_eq!(items, xbel_bank_items());
+
+        // Compact: a single line
+        let mut output = vec![];
+        print_items(
+            XbelNestingIterator::new(&xbel),
+            PrintFormat::Json,
+            &PrintOptions::default(),
+            &mut output,
+        )
+        .unwrap();
+        let compact = String::from_utf8(output).unwrap();
+        assert_eq!(compact.lines().count(), 1);
+        assert_eq!(
+            serde_json::from_str::<Vec<XbelItem>>(&compact).unwrap(),
+            xbel_bank_items()
+        );
+
+        // Hidden items are not exported
+        let json = print_to_string(&xbel, PrintFormat::Json, Some(0));
+        let items: Vec<XbelItem> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            items,
+            vec![
+                XbelItem::new_folder("1", "admin", None),
+                XbelItem::new_bookmark("5", "https://example.com/", "Example"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_html() {
+        let xbel = xbel_bank();
+        let html = print_to_string(&xbel, PrintFormat::Html, None);
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n"));
+        assert!(html.contains("\n    <DT><H3>admin</H3>\n    <DL><p>\n        <DT><H3>bank</H3>\n"));
+        assert!(html.contains(
+            "\n        <DT><A HREF=\"https://www.bank2.com/?a=1&amp;b=2\">Bank &lt;2&gt;</A>\n"
+        ));
+        assert!(html.ends_with(
+            "\n    </DL><p>\n    <DT><A HREF=\"https://example.com/\">Example</A>\n</DL><p>\n"
+        ));
+    }
+}
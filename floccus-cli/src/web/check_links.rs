@@ -0,0 +1,113 @@
+// std
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Result of a link check (http HEAD request, falling back to GET)
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkStatus {
+    /// Http status 2xx (after redirects)
+    Alive(u16),
+    /// Any other http status (e.g. 404)
+    Status(u16),
+    /// Request failed (e.g. dns error, timeout, connection refused)
+    Error(String),
+}
+
+/// Check a link: http HEAD request, then GET if HEAD is rejected (some servers do not support it)
+pub fn check_link(agent: &ureq::Agent, url: &str) -> LinkStatus {
+    let status = |res: Result<ureq::Response, ureq::Error>| match res {
+        Ok(response) if (200..300).contains(&response.status()) => {
+            LinkStatus::Alive(response.status())
+        }
+        Ok(response) => LinkStatus::Status(response.status()),
+        Err(ureq::Error::Status(code, _response)) => LinkStatus::Status(code),
+        Err(e) => LinkStatus::Error(e.to_string()),
+    };
+
+    match status(agent.head(url).call()) {
+        LinkStatus::Alive(code) => LinkStatus::Alive(code),
+        _ => status(agent.get(url).call()),
+    }
+}
+
+/// Check links using `concurrency` threads (each request is limited to `timeout`)
+///
+/// Returns the status of each link (in the same order as `urls`)
+pub fn check_links(urls: &[&str], concurrency: usize, timeout: Duration) -> Vec<LinkStatus> {
+    let agent = ureq::AgentBuilder::new().timeout(timeout).build();
+    let to_check = Mutex::new(urls.iter().enumerate());
+    let checked = Mutex::new(Vec::with_capacity(urls.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.max(1).min(urls.len()) {
+            scope.spawn(|| loop {
+                // Note: the lock is released before the request
+                let next = to_check.lock().unwrap().next();
+                let Some((index, url)) = next else {
+                    break;
+                };
+                let status = check_link(&agent, url);
+                checked.lock().unwrap().push((index, status));
+            });
+        }
+    });
+
+    let mut checked = checked.into_inner().unwrap();
+    checked.sort_by_key(|(index, _status)| *index);
+    checked.into_iter().map(|(_index, status)| status).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_check_links() {
+        // Minimal http server: 200 for /ok, 405 for HEAD /get-only (but 200 for GET), else 404
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let status = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+                    [_, "/ok", _] | ["GET", "/get-only", _] => "200 OK",
+                    ["HEAD", "/get-only", _] => "405 Method Not Allowed",
+                    _ => "404 Not Found",
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let ok = format!("http://{}/ok", address);
+        let get_only = format!("http://{}/get-only", address);
+        let missing = format!("http://{}/missing", address);
+        let statuses = check_links(
+            &[ok.as_str(), missing.as_str(), get_only.as_str(), "http://"],
+            2,
+            Duration::from_secs(5),
+        );
+        assert_eq!(statuses.len(), 4);
+        assert_eq!(statuses[0], LinkStatus::Alive(200));
+        assert_eq!(statuses[1], LinkStatus::Status(404));
+        assert_eq!(statuses[2], LinkStatus::Alive(200));
+        assert!(
+            matches!(statuses[3], LinkStatus::Error(_)),
+            "{:?}",
+            statuses
+        );
+    }
+}
@@ -0,0 +1,97 @@
+// std
+use std::io::Read;
+use std::sync::LazyLock;
+use std::time::Duration;
+// third-party
+use regex::{Captures, Regex};
+use thiserror::Error;
+
+const FETCH_TITLE_TIMEOUT: Duration = Duration::from_secs(10);
+// Only read the beginning of the page (the title is expected in the html head)
+const FETCH_TITLE_MAX_SIZE: u64 = 512 * 1024;
+
+static TITLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+static NUMERIC_ENTITY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&#([xX][0-9a-fA-F]+|[0-9]+);").unwrap());
+
+#[derive(Error, Debug)]
+pub enum FetchTitleError {
+    #[error("Http request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("Cannot read http response: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No title found in page")]
+    NoTitle,
+}
+
+/// Get a web page (http get) and extract its title
+pub fn fetch_title(url: &str) -> Result<String, FetchTitleError> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(FETCH_TITLE_TIMEOUT)
+        .build();
+    let response = agent.get(url).call().map_err(Box::new)?;
+
+    let mut content = Vec::new();
+    response
+        .into_reader()
+        .take(FETCH_TITLE_MAX_SIZE)
+        .read_to_end(&mut content)?;
+
+    extract_title(String::from_utf8_lossy(&content).as_ref()).ok_or(FetchTitleError::NoTitle)
+}
+
+/// Extract the content of the html title tag (whitespaces collapsed & html entities decoded)
+fn extract_title(html: &str) -> Option<String> {
+    let title = TITLE_RE.captures(html)?.get(1)?.as_str();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = decode_html_entities(title.as_str());
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+fn decode_html_entities(s: &str) -> String {
+    let s = NUMERIC_ENTITY_RE.replace_all(s, |caps: &Captures| {
+        let code = &caps[1];
+        let code = match code.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => code.parse::<u32>().ok(),
+        };
+        code.and_then(char::from_u32)
+            .map(String::from)
+            .unwrap_or_else(|| caps[0].to_string())
+    });
+
+    // Note: &amp; must be the last one
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_title() {
+        let html = r#"<html><head>
+            <meta charset="utf-8">
+            <TITLE lang="en">
+              Rust &amp; Cargo &#8211; &#x1F980;
+            </TITLE>
+            </head><body><title>Not this one</title></body></html>"#;
+        assert_eq!(
+            extract_title(html),
+            Some("Rust & Cargo \u{2013} \u{1F980}".to_string())
+        );
+        assert_eq!(extract_title("<html><title>  </title></html>"), None);
+        assert_eq!(extract_title("<html><head></head></html>"), None);
+    }
+}
@@ -0,0 +1,101 @@
+// std
+use std::time::Duration;
+// third-party
+use floccus_xbel::{Xbel, XbelError};
+use thiserror::Error;
+
+const FETCH_XBEL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Error, Debug)]
+pub enum FetchXbelError {
+    #[error("Http error {status} ({status_text}) for: {url}")]
+    Status {
+        url: String,
+        status: u16,
+        status_text: String,
+    },
+    /// Request failed (e.g. dns error, timeout, connection refused)
+    #[error("Http request failed: {0}")]
+    Transport(Box<ureq::Transport>),
+    #[error("Cannot read Xbel document from: {url}: {source}")]
+    Xbel { url: String, source: XbelError },
+}
+
+/// Get a Xbel document (http get) and parse it (see `Xbel::try_from_reader`)
+pub fn fetch_xbel(url: &str) -> Result<Xbel, FetchXbelError> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(FETCH_XBEL_TIMEOUT)
+        .build();
+    let response = agent.get(url).call().map_err(|e| match e {
+        ureq::Error::Status(status, response) => FetchXbelError::Status {
+            url: url.to_string(),
+            status,
+            status_text: response.status_text().to_string(),
+        },
+        ureq::Error::Transport(transport) => FetchXbelError::Transport(Box::new(transport)),
+    })?;
+
+    Xbel::try_from_reader(response.into_reader()).map_err(|source| FetchXbelError::Xbel {
+        url: url.to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+<bookmark href="https://www.example.com/" id="1"><title>Example</title></bookmark>
+</xbel>"#;
+
+    #[test]
+    fn test_fetch_xbel() {
+        // Minimal http server: a Xbel document for /bookmarks.xbel, html for /page, else 404
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                reader.read_line(&mut request_line).unwrap();
+                // Skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let (status, body) = match request_line.split_whitespace().nth(1) {
+                    Some("/bookmarks.xbel") => ("200 OK", XBEL),
+                    Some("/page") => ("200 OK", "<html><title>Not a Xbel</title></html>"),
+                    _ => ("404 Not Found", ""),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let xbel = fetch_xbel(format!("http://{}/bookmarks.xbel", address).as_str()).unwrap();
+        assert_eq!(
+            xbel.get_bookmark(1).unwrap().href,
+            "https://www.example.com/"
+        );
+
+        let res = fetch_xbel(format!("http://{}/missing", address).as_str());
+        assert!(
+            matches!(res, Err(FetchXbelError::Status { status: 404, .. })),
+            "{:?}",
+            res
+        );
+        let res = fetch_xbel(format!("http://{}/page", address).as_str());
+        assert!(matches!(res, Err(FetchXbelError::Xbel { .. })), "{:?}", res);
+    }
+}
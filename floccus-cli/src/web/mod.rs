@@ -0,0 +1,13 @@
+#[cfg(feature = "check-links")]
+mod check_links;
+#[cfg(feature = "fetch-title")]
+mod fetch_title;
+#[cfg(feature = "fetch-xbel")]
+mod fetch_xbel;
+
+#[cfg(feature = "check-links")]
+pub use check_links::{check_links, LinkStatus};
+#[cfg(feature = "fetch-title")]
+pub use fetch_title::fetch_title;
+#[cfg(feature = "fetch-xbel")]
+pub use fetch_xbel::{fetch_xbel, FetchXbelError};
@@ -0,0 +1,229 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use git2::{Repository, Signature};
+
+const XBEL_BANK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+<xbel version="1.0">
+<!--- highestId :3: for Floccus bookmark sync browser extension -->
+
+<folder id="1">
+  <title>bank</title>
+  <bookmark href="https://www.bank1.com/" id="2">
+    <title>Bank 1</title>
+  </bookmark>
+  <bookmark href="https://www.bank2.com/" id="3">
+    <title>Bank 2</title>
+  </bookmark>
+</folder>
+</xbel>"#;
+
+/// Create a git repository (branch main) with a bookmarks.xbel file
+fn create_remote(path: &Path) {
+    let repo = Repository::init(path).unwrap();
+    std::fs::write(path.join("bookmarks.xbel"), XBEL_BANK).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("bookmarks.xbel")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::now("Floccus cli", "floccus-cli@example.com").unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+        .unwrap();
+}
+
+/// Run floccus-cli find (with HOME set to the tmp dir)
+fn run_find(tmp_dir: &Path, repository_folder: &Path, remote: &Path, args: &[&str]) -> Output {
+    let remote_url = format!("file://{}", remote.display());
+    Command::new(env!("CARGO_BIN_EXE_floccus-cli"))
+        .env("HOME", tmp_dir)
+        .env_remove("FLOCCUS_CLI_CONFIG")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_CACHE_HOME")
+        .args(["-q", "-r"])
+        .arg(repository_folder)
+        .args(["-g", remote_url.as_str(), "find"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn find_on_fresh_repository_folder() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let remote = tmp_dir.path().join("remote");
+    create_remote(&remote);
+    let repository_folder = tmp_dir.path().join("local");
+
+    // The repository folder does not exist yet: find must clone it first
+    let output = run_find(tmp_dir.path(), &repository_folder, &remote, &["Bank 2"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.starts_with("Found 1 folder or bookmark:"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("https://www.bank2.com/"));
+    assert!(repository_folder.join("bookmarks.xbel").exists());
+}
+
+#[test]
+fn find_with_limit() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let remote = tmp_dir.path().join("remote");
+    create_remote(&remote);
+    let repository_folder = tmp_dir.path().join("local");
+
+    let output = run_find(
+        tmp_dir.path(),
+        &repository_folder,
+        &remote,
+        &["--bookmark", "--limit", "1", "Bank"],
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // Total number of results is reported, only the first one is printed
+    assert!(stdout.starts_with("Found 2 bookmarks:"), "{}", stdout);
+    assert!(stdout.contains("https://www.bank1.com/"));
+    assert!(!stdout.contains("https://www.bank2.com/"));
+    assert!(stdout.trim_end().ends_with("... and 1 more"), "{}", stdout);
+}
+
+#[test]
+fn find_with_exit_code() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let remote = tmp_dir.path().join("remote");
+    create_remote(&remote);
+    let repository_folder = tmp_dir.path().join("local");
+    let run = |args: &[&str]| run_find(tmp_dir.path(), &repository_folder, &remote, args);
+
+    // Default: always exit 0
+    assert_eq!(run(&["Nothing like this"]).status.code(), Some(0));
+    // --exit-code: 1 if nothing is found
+    assert_eq!(
+        run(&["--exit-code", "Nothing like this"]).status.code(),
+        Some(1)
+    );
+    assert_eq!(run(&["--exit-code", "Bank 1"]).status.code(), Some(0));
+}
+
+#[test]
+fn find_by_id() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let remote = tmp_dir.path().join("remote");
+    create_remote(&remote);
+    let repository_folder = tmp_dir.path().join("local");
+    let run = |args: &[&str]| run_find(tmp_dir.path(), &repository_folder, &remote, args);
+
+    let output = run(&["--id", "3", "--show-path"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.starts_with("Found 1 folder or bookmark:\n0- bank > "),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("https://www.bank2.com/"));
+    assert!(!stdout.contains("https://www.bank1.com/"));
+
+    // Unknown id
+    let output = run(&["--id", "42"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No folder or bookmark with id: 42"));
+    // Cannot be used with a search text
+    assert!(!run(&["--id", "3", "Bank"]).status.success());
+}
+
+#[test]
+fn find_with_glob() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let remote = tmp_dir.path().join("remote");
+    create_remote(&remote);
+    let repository_folder = tmp_dir.path().join("local");
+    let run = |args: &[&str]| run_find(tmp_dir.path(), &repository_folder, &remote, args);
+
+    // Url without its scheme
+    let output = run(&["--glob", "--url", "www.bank[2-9].com/*"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("Found 1 folder or bookmark:"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("https://www.bank2.com/"));
+    // Whole title
+    let output = run(&["--glob", "--title", "Bank ?"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("Found 2 folders or bookmarks:"),
+        "{}",
+        stdout
+    );
+    let output = run(&["--glob", "--title", "Bank"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Found 0"), "{}", stdout);
+    // Invalid pattern
+    let output = run(&["--glob", "[a-"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid glob pattern"));
+}
+
+#[test]
+fn find_from_stdin() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let run_with_stdin = |args: &[&str]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_floccus-cli"))
+            .env("HOME", tmp_dir.path())
+            .env_remove("FLOCCUS_CLI_CONFIG")
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .arg("-q")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // Note: stdin is closed when dropped (end of the Xbel document)
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(XBEL_BANK.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    // No git repository is cloned (nor any bookmark file read)
+    let output = run_with_stdin(&["find", "--stdin", "Bank 1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.starts_with("Found 1 folder or bookmark:"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("https://www.bank1.com/"));
+
+    // Only for read only commands & without any repository
+    let output = run_with_stdin(&["rm", "--stdin", "-i", "2"]);
+    assert!(!output.status.success());
+    let output = run_with_stdin(&["-r", "/tmp", "print", "--stdin"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--stdin"));
+}
@@ -0,0 +1,126 @@
+use std::process::Command;
+
+const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+<!--- highestId :1: for Floccus bookmark sync browser extension -->
+<bookmark href="https://www.example.com/" id="1"><title>Example</title></bookmark>
+</xbel>"#;
+
+const XBEL_NO_NUMERIC_ID: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+<!--- highestId :2: for Floccus bookmark sync browser extension -->
+<bookmark href="https://old.example.com/a" id="1"><title>Old A</title></bookmark>
+<bookmark href="https://old.example.com/b" id="b"><title>Old B</title></bookmark>
+<bookmark href="https://www.example.com/" id="2"><title>Example</title></bookmark>
+</xbel>"#;
+
+/// Run floccus-cli on a Xbel file (--xbel), returns the output and the file content
+fn run_xbel(xbel: &str, args: &[&str]) -> (std::process::Output, String) {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let xbel_path = tmp_dir.path().join("bookmarks.xbel");
+    std::fs::write(&xbel_path, xbel).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_floccus-cli"))
+        .env("HOME", tmp_dir.path())
+        .env_remove("FLOCCUS_CLI_CONFIG")
+        .env_remove("XDG_CONFIG_HOME")
+        .arg("-q")
+        .arg("--xbel")
+        .arg(&xbel_path)
+        .args(args)
+        .output()
+        .unwrap();
+    let content = std::fs::read_to_string(&xbel_path).unwrap();
+    (output, content)
+}
+
+#[test]
+fn add_from_file_keep_going() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let xbel_path = tmp_dir.path().join("bookmarks.xbel");
+    std::fs::write(&xbel_path, XBEL).unwrap();
+    let list_path = tmp_dir.path().join("bookmarks.txt");
+    std::fs::write(
+        &list_path,
+        "https://www.rust-lang.org/\tRust\nnot an url\nhttps://crates.io/\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_floccus-cli"))
+        .env("HOME", tmp_dir.path())
+        .env_remove("FLOCCUS_CLI_CONFIG")
+        .env_remove("XDG_CONFIG_HOME")
+        .arg("-q")
+        .arg("--xbel")
+        .arg(&xbel_path)
+        .args(["add", "--no-fetch-title", "--keep-going", "--from-file"])
+        .arg(&list_path)
+        .output()
+        .unwrap();
+
+    // The valid lines are added but the command fails (a line is malformed)
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(stdout.contains("2 succeeded, 1 failed"), "{}", stdout);
+    assert!(
+        stdout.contains("Malformed line 2: not an url"),
+        "{}",
+        stdout
+    );
+    let content = std::fs::read_to_string(&xbel_path).unwrap();
+    assert!(content.contains("https://www.rust-lang.org/"));
+    assert!(content.contains("https://crates.io/"));
+}
+
+#[test]
+fn rm_match_abort() {
+    // A matching bookmark has no numeric id: nothing is removed
+    let (output, content) = run_xbel(XBEL_NO_NUMERIC_ID, &["rm", "--match", "old", "--yes"]);
+    assert!(!output.status.success());
+    assert_eq!(content, XBEL_NO_NUMERIC_ID);
+}
+
+#[test]
+fn rm_match_keep_going() {
+    let (output, content) = run_xbel(
+        XBEL_NO_NUMERIC_ID,
+        &["rm", "--match", "old", "--yes", "--keep-going"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(stdout.contains("1 succeeded, 1 failed"), "{}", stdout);
+    assert!(!content.contains("https://old.example.com/a"));
+    assert!(content.contains("https://old.example.com/b"));
+    assert!(content.contains("https://www.example.com/"));
+}
+
+/// Rewrite "https://old.example.com/a" to "a:x" but "https://www.example.com/" to ":x" (invalid)
+const REWRITE_ARGS: [&str; 6] = [
+    "rewrite-urls",
+    "--regex",
+    "--from",
+    r"^https://(\w+)\.example\.com/(\w*)$",
+    "--to",
+    "$2:x",
+];
+
+#[test]
+fn rewrite_urls_abort() {
+    // An url would become invalid: nothing is rewritten
+    let (output, content) = run_xbel(XBEL_NO_NUMERIC_ID, &REWRITE_ARGS);
+    assert!(!output.status.success());
+    assert_eq!(content, XBEL_NO_NUMERIC_ID);
+}
+
+#[test]
+fn rewrite_urls_keep_going() {
+    let (output, content) = run_xbel(
+        XBEL_NO_NUMERIC_ID,
+        &[&REWRITE_ARGS[..], &["--keep-going"]].concat(),
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "{}", stdout);
+    assert!(stdout.contains("2 succeeded, 1 failed"), "{}", stdout);
+    assert!(content.contains(r#"href="a:x""#), "{}", content);
+    assert!(content.contains(r#"href="b:x""#), "{}", content);
+    assert!(content.contains("https://www.example.com/"), "{}", content);
+}
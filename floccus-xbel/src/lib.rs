@@ -1,6 +1,14 @@
 //! A crate to handle the XMLBookmarkExchangeLanguage format (Xbel) used by Floccus
 
+pub mod xbel_diff;
 pub mod xbel_format;
+pub mod xbel_reader;
+pub mod xbel_validate;
 
-pub use xbel_format::{Xbel, XbelError, XbelItem, XbelPath};
-pub use xbel_format::{XbelItemOrEnd, XbelNestingIterator};
+pub use xbel_diff::{diff, XbelChange};
+pub use xbel_format::XbelPath;
+pub use xbel_format::{Bookmark, Folder, Parent, Placement, Resolved, Title};
+pub use xbel_format::{WriteOptions, Xbel, XbelError, XbelInsertError, XbelItem, XbelMoveError};
+pub use xbel_format::{XbelBookmarksMut, XbelDepthIterator, XbelItemOrEnd, XbelNestingIterator};
+pub use xbel_reader::{XbelReadEvent, XbelReader};
+pub use xbel_validate::ValidationIssue;
@@ -0,0 +1,229 @@
+// std
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+// internal
+use crate::xbel_format::{Xbel, XbelItem};
+
+/// A change between two `Xbel`, items are matched using their id
+#[derive(Debug, Clone, PartialEq)]
+pub enum XbelChange {
+    /// Item only found in the second Xbel
+    Added {
+        id: String,
+        title: String,
+        url: Option<String>,
+    },
+    /// Item only found in the first Xbel
+    Removed {
+        id: String,
+        title: String,
+        url: Option<String>,
+    },
+    /// Item moved to another folder (parent folder id or None for root)
+    Moved {
+        id: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// Item title changed
+    TitleChanged {
+        id: String,
+        from: String,
+        to: String,
+    },
+    /// Bookmark url changed
+    UrlChanged {
+        id: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Display for XbelChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parent = |p: &Option<String>| p.clone().unwrap_or("root".to_string());
+        match self {
+            XbelChange::Added { id, title, url } => match url {
+                Some(url) => write!(f, "+ [{}] {} ({})", id, title, url),
+                None => write!(f, "+ [{}] {}", id, title),
+            },
+            XbelChange::Removed { id, title, url } => match url {
+                Some(url) => write!(f, "- [{}] {} ({})", id, title, url),
+                None => write!(f, "- [{}] {}", id, title),
+            },
+            XbelChange::Moved { id, from, to } => {
+                write!(f, "~ [{}] moved: {} -> {}", id, parent(from), parent(to))
+            }
+            XbelChange::TitleChanged { id, from, to } => {
+                write!(f, "~ [{}] title: {} -> {}", id, from, to)
+            }
+            XbelChange::UrlChanged { id, from, to } => {
+                write!(f, "~ [{}] url: {} -> {}", id, from, to)
+            }
+        }
+    }
+}
+
+/// Map an item id to its parent folder id (None for root) and the item
+type ItemIndex<'a> = HashMap<&'a str, (Option<&'a str>, &'a XbelItem)>;
+
+fn index_items<'a>(items: &'a [XbelItem], parent: Option<&'a str>, index: &mut ItemIndex<'a>) {
+    for item in items.iter().filter(|item| !is_alias(item)) {
+        index.insert(item.get_id().as_str(), (parent, item));
+        if let XbelItem::Folder(f) = item {
+            index_items(&f.items, Some(f.id.as_str()), index);
+        }
+    }
+}
+
+/// An alias has no id (so it cannot be matched and is ignored by `diff`)
+fn is_alias(item: &XbelItem) -> bool {
+    matches!(item, XbelItem::Alias { .. })
+}
+
+/// Compare 2 Xbel and return the list of changes (from `a` to `b`)
+///
+/// Removed, moved and modified items are reported first (in `a` DFS order) then added items
+/// (in `b` DFS order). Aliases (`XbelItem::Alias`) are ignored.
+pub fn diff(a: &Xbel, b: &Xbel) -> Vec<XbelChange> {
+    let mut index_a = ItemIndex::new();
+    index_items(&a.items, None, &mut index_a);
+    let mut index_b = ItemIndex::new();
+    index_items(&b.items, None, &mut index_b);
+
+    let mut changes = vec![];
+    for item_a in a.into_iter().filter(|item| !is_alias(item)) {
+        let id = item_a.get_id();
+        match index_b.get(id.as_str()) {
+            None => changes.push(XbelChange::Removed {
+                id: id.clone(),
+                title: item_a.get_title().text.clone(),
+                url: item_a.get_url().cloned(),
+            }),
+            Some((parent_b, item_b)) => {
+                let (parent_a, _) = index_a[id.as_str()];
+                if parent_a != *parent_b {
+                    changes.push(XbelChange::Moved {
+                        id: id.clone(),
+                        from: parent_a.map(|p| p.to_string()),
+                        to: parent_b.map(|p| p.to_string()),
+                    });
+                }
+                if item_a.get_title() != item_b.get_title() {
+                    changes.push(XbelChange::TitleChanged {
+                        id: id.clone(),
+                        from: item_a.get_title().text.clone(),
+                        to: item_b.get_title().text.clone(),
+                    });
+                }
+                if let (Some(url_a), Some(url_b)) = (item_a.get_url(), item_b.get_url()) {
+                    if url_a != url_b {
+                        changes.push(XbelChange::UrlChanged {
+                            id: id.clone(),
+                            from: url_a.clone(),
+                            to: url_b.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    changes.extend(
+        b.into_iter()
+            .filter(|item_b| !is_alias(item_b) && !index_a.contains_key(item_b.get_id().as_str()))
+            .map(|item_b| XbelChange::Added {
+                id: item_b.get_id().clone(),
+                title: item_b.get_title().text.clone(),
+                url: item_b.get_url().cloned(),
+            }),
+    );
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_test::traced_test;
+
+    const XBEL_BANK: &str = r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <xbel version="1.0">
+            <!--- highestId :5: for Floccus bookmark sync browser extension -->
+            <folder id="1">
+                <title>admin</title>
+                <folder id="2">
+                    <title>bank</title>
+                    <bookmark href="https://www.bank1.com/" id="3">
+                        <title>Bank 1</title>
+                    </bookmark>
+                    <bookmark href="https://www.bank2.com" id="4">
+                        <title>Bank 2</title>
+                    </bookmark>
+                </folder>
+                <bookmark href="https://www.bank3.com" id="5">
+                    <title>Bank 3</title>
+                </bookmark>
+            </folder>
+            </xbel>
+        "#;
+
+    const XBEL_BANK_2: &str = r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <xbel version="1.0">
+            <!--- highestId :6: for Floccus bookmark sync browser extension -->
+            <folder id="1">
+                <title>admin</title>
+                <folder id="2">
+                    <title>banks</title>
+                    <bookmark href="https://www.bank1.com/" id="3">
+                        <title>Bank 1</title>
+                    </bookmark>
+                    <bookmark href="https://www.bank3.com" id="5">
+                        <title>Bank 3</title>
+                    </bookmark>
+                </folder>
+            </folder>
+            <bookmark href="https://www.bank6.com" id="6">
+                <title>Bank 6</title>
+            </bookmark>
+            </xbel>
+        "#;
+
+    #[test]
+    #[traced_test]
+    fn xbel_diff() -> Result<(), crate::XbelError> {
+        let a = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let b = Xbel::try_from_reader(XBEL_BANK_2.as_bytes())?;
+
+        assert!(diff(&a, &a).is_empty());
+        let changes = diff(&a, &b);
+        assert_eq!(
+            changes,
+            vec![
+                XbelChange::TitleChanged {
+                    id: "2".to_string(),
+                    from: "bank".to_string(),
+                    to: "banks".to_string()
+                },
+                XbelChange::Removed {
+                    id: "4".to_string(),
+                    title: "Bank 2".to_string(),
+                    url: Some("https://www.bank2.com".to_string())
+                },
+                XbelChange::Moved {
+                    id: "5".to_string(),
+                    from: Some("1".to_string()),
+                    to: Some("2".to_string())
+                },
+                XbelChange::Added {
+                    id: "6".to_string(),
+                    title: "Bank 6".to_string(),
+                    url: Some("https://www.bank6.com".to_string())
+                },
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,18 +1,23 @@
 // std
-use std::collections::VecDeque;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{Display, Formatter};
-use std::io::{BufReader, Write};
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
+use std::str::FromStr;
 // third-party
-use quick_xml::de::from_reader;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::reader::Reader;
 use quick_xml::writer::Writer;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 // internal
 
 /// The title of a `Bookmark` or `Folder`
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(default, rename = "lowercase")]
 pub struct Title {
     #[serde(rename = "$text")]
@@ -20,76 +25,232 @@ pub struct Title {
 }
 
 impl Title {
-    fn new(title: &str) -> Self {
+    pub fn new(title: &str) -> Self {
         Self {
             text: title.to_string(),
         }
     }
+
+    /// Trim and collapse internal whitespace (including newlines) into a single space
+    ///
+    /// Returns true if the title text was changed
+    pub fn normalize(&mut self) -> bool {
+        let normalized = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if normalized != self.text {
+            self.text = normalized;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 /// A Bookmark aka a `Title` and usually a www url
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(default, rename = "lowercase")]
 pub struct Bookmark {
     #[serde(rename = "@href")]
     pub href: String,
     #[serde(rename = "@id")]
     pub id: String,
+    /// Creation date (Xbel `added` attribute, ISO 8601 e.g. 2024-03-01T10:00:00) if any
+    #[serde(rename = "@added", skip_serializing_if = "Option::is_none")]
+    pub added: Option<String>,
+    /// Tags (floccus-cli `tags` attribute: comma separated tags e.g. `tags="rust,dev"`) if any,
+    /// see `Bookmark::tags` and `Bookmark::set_tags`
+    #[serde(rename = "@tags", skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
     pub title: Title,
 }
 
 impl Bookmark {
-    fn new(id: &str, url: &str, title: &str) -> Self {
+    /// Create a new bookmark
+    ///
+    /// Ids must be unique (among all folders and bookmarks) and are expected to be integers (as
+    /// written by Floccus). When adding to an existing `Xbel`, prefer `Xbel::new_bookmark` which
+    /// allocates the next available id.
+    pub fn new(id: &str, url: &str, title: &str) -> Self {
         Self {
             href: url.to_string(),
             id: id.to_string(),
+            added: None,
+            tags: None,
             title: Title::new(title),
         }
     }
+
+    /// Separator between tags in the `tags` attribute
+    pub const TAG_SEPARATOR: char = ',';
+
+    /// Bookmark tags (trimmed, empty tags are skipped)
+    pub fn tags(&self) -> Vec<&str> {
+        self.tags
+            .as_deref()
+            .unwrap_or_default()
+            .split(Self::TAG_SEPARATOR)
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// Set the bookmark tags (trimmed, empty tags are skipped, no `tags` attribute if no tags)
+    ///
+    /// A tag cannot contain `TAG_SEPARATOR` (it would be read as 2 tags).
+    pub fn set_tags<S: AsRef<str>>(&mut self, tags: &[S]) {
+        let tags = tags
+            .iter()
+            .map(|tag| tag.as_ref().trim())
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vec<_>>();
+        self.tags = (!tags.is_empty()).then(|| tags.join(&Self::TAG_SEPARATOR.to_string()));
+    }
+
+    /// Set the bookmark url
+    pub fn set_href(&mut self, href: &str) {
+        self.href = href.to_string();
+    }
+
+    /// Bookmark has the given tag (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase();
+        self.tags().iter().any(|t| t.to_lowercase() == tag)
+    }
 }
 
 /// An enum that is either a `Folder` or a `Bookmark`. See `XbelIterator` or `XbelNestingIterator`.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum XbelItem {
     #[serde(rename = "folder")]
     Folder(Folder),
     #[serde(rename = "bookmark")]
     Bookmark(Bookmark),
+    /// A reference to another bookmark or folder (by id), see `Xbel::resolve_alias`
+    #[serde(rename = "alias")]
+    Alias {
+        #[serde(rename = "@ref")]
+        reference: String,
+    },
 }
 
 impl XbelItem {
-    pub(crate) fn new_bookmark(id: &str, url: &str, title: &str) -> Self {
+    /// Create a new bookmark item (see `Bookmark::new` for id expectations)
+    pub fn new_bookmark(id: &str, url: &str, title: &str) -> Self {
         Self::Bookmark(Bookmark::new(id, url, title))
     }
+
+    /// Create a new folder item (see `Folder::new` for id expectations)
+    pub fn new_folder(id: &str, title: &str, items: Option<Vec<XbelItem>>) -> Self {
+        Self::Folder(Folder::new(id, title, items))
+    }
 }
 
+/// Title of an alias (an alias has no title, see `XbelItem::get_title`)
+static ALIAS_TITLE: Title = Title {
+    text: String::new(),
+};
+/// Id of an alias (an alias has no id, see `XbelItem::get_id`)
+static ALIAS_ID: String = String::new();
+
 impl XbelItem {
-    /// Get title of Bookmark or Folder
+    /// Get title of Bookmark or Folder (empty for an Alias)
     pub fn get_title(&self) -> &Title {
         match self {
             XbelItem::Folder(f) => &f.title,
             XbelItem::Bookmark(b) => &b.title,
+            XbelItem::Alias { .. } => &ALIAS_TITLE,
+        }
+    }
+    /// Get mutable title of Bookmark or Folder or None if it's an Alias
+    pub fn get_title_mut(&mut self) -> Option<&mut Title> {
+        match self {
+            XbelItem::Folder(f) => Some(&mut f.title),
+            XbelItem::Bookmark(b) => Some(&mut b.title),
+            XbelItem::Alias { .. } => None,
+        }
+    }
+    /// Set title of Bookmark or Folder (an Alias is left unchanged)
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(item_title) = self.get_title_mut() {
+            *item_title = Title::new(title);
         }
     }
-    /// Get id of Bookmark or Folder
+    /// Get id of Bookmark or Folder (empty for an Alias)
     pub fn get_id(&self) -> &String {
         match self {
             XbelItem::Folder(f) => &f.id,
             XbelItem::Bookmark(b) => &b.id,
+            XbelItem::Alias { .. } => &ALIAS_ID,
         }
     }
 
-    /// Get the url of a Bookmark or None if it's a Folder
+    /// The id as an integer (None if missing or not an integer, e.g. for an Alias)
+    pub fn numeric_id(&self) -> Option<u64> {
+        self.get_id().parse::<u64>().ok()
+    }
+
+    /// The id attribute is missing (or empty), see `Xbel::assign_missing_ids`
+    ///
+    /// Always false for an Alias (which has no id attribute).
+    pub fn has_missing_id(&self) -> bool {
+        !matches!(self, XbelItem::Alias { .. }) && self.get_id().trim().is_empty()
+    }
+
+    /// Get the url of a Bookmark or None if it's a Folder or an Alias
     pub fn get_url(&self) -> Option<&String> {
         match self {
-            XbelItem::Folder(_f) => None,
             XbelItem::Bookmark(b) => Some(&b.href),
+            XbelItem::Folder(_) | XbelItem::Alias { .. } => None,
+        }
+    }
+
+    /// Get the mutable url of a Bookmark or None if it's a Folder or an Alias (see `get_url`)
+    pub fn get_url_mut(&mut self) -> Option<&mut String> {
+        match self {
+            XbelItem::Bookmark(b) => Some(&mut b.href),
+            XbelItem::Folder(_) | XbelItem::Alias { .. } => None,
         }
     }
+
+    /// Compare 2 items by title (folders before bookmarks)
+    ///
+    /// Titles are compared case-insensitively if `case_insensitive` is true (then by exact title,
+    /// so the ordering stays deterministic).
+    pub fn cmp_by_title(&self, other: &XbelItem, case_insensitive: bool) -> Ordering {
+        self.cmp_kind(other).then_with(|| {
+            cmp_text(
+                &self.get_title().text,
+                &other.get_title().text,
+                case_insensitive,
+            )
+        })
+    }
+
+    /// Compare 2 items by url (folders before bookmarks, folders are compared by title)
+    ///
+    /// Urls (and titles) are compared case-insensitively if `case_insensitive` is true, then by
+    /// exact url. Bookmarks with the same url are compared by title.
+    pub fn cmp_by_url(&self, other: &XbelItem, case_insensitive: bool) -> Ordering {
+        self.cmp_kind(other)
+            .then_with(|| match (self.get_url(), other.get_url()) {
+                (Some(url), Some(other_url)) => cmp_text(url, other_url, case_insensitive),
+                _ => Ordering::Equal,
+            })
+            .then_with(|| self.cmp_by_title(other, case_insensitive))
+    }
+
+    /// Folders before bookmarks (then aliases)
+    fn cmp_kind(&self, other: &XbelItem) -> Ordering {
+        let kind = |item: &XbelItem| match item {
+            XbelItem::Folder(_) => 0,
+            XbelItem::Bookmark(_) => 1,
+            XbelItem::Alias { .. } => 2,
+        };
+        kind(self).cmp(&kind(other))
+    }
 }
 
 /// A Folder that contains folders and bookmarks
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(default, rename = "lowercase")]
 pub struct Folder {
     #[serde(rename = "@id")]
@@ -100,14 +261,118 @@ pub struct Folder {
 }
 
 impl Folder {
-    #[allow(dead_code)]
-    fn new(id: &str, title: &str, items: Option<Vec<XbelItem>>) -> Self {
+    /// Create a new folder (with optional children)
+    ///
+    /// Ids must be unique (among all folders and bookmarks) and are expected to be integers (as
+    /// written by Floccus). When adding to an existing `Xbel`, prefer `Xbel::new_folder` which
+    /// allocates the next available id.
+    pub fn new(id: &str, title: &str, items: Option<Vec<XbelItem>>) -> Self {
         Self {
             id: id.to_string(),
             title: Title::new(title),
             items: items.unwrap_or_default(),
         }
     }
+
+    /// Folder has no children (no folders and no bookmarks)
+    ///
+    /// Note: Xbel separators are not part of `XbelItem`, so they never count as content.
+    pub fn is_empty(&self) -> bool {
+        self.child_count() == 0
+    }
+
+    /// Number of direct children (folders and bookmarks, separators excluded)
+    pub fn child_count(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Maximum nesting depth of the folder content (0: empty folder, 1: no sub folders)
+    ///
+    /// See `Xbel::max_depth`
+    pub fn max_depth(&self) -> usize {
+        self.items
+            .iter()
+            .map(|item| nesting_depth(XbelNestingIterator::from_item(item)))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Count all descendants (recursively): (folder count, bookmark count), aliases are not counted
+    pub fn count_descendants(&self) -> (usize, usize) {
+        self.items.iter().flat_map(XbelIterator::from_item).fold(
+            (0, 0),
+            |(folders, bookmarks), item| match item {
+                XbelItem::Folder(_) => (folders + 1, bookmarks),
+                XbelItem::Bookmark(_) => (folders, bookmarks + 1),
+                XbelItem::Alias { .. } => (folders, bookmarks),
+            },
+        )
+    }
+}
+
+/// Where to insert an item relatively to a target item (see `Xbel::move_item`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placement {
+    /// Before the target item (same folder)
+    Before,
+    /// After the target item (same folder)
+    After,
+    /// In the target folder (as first item)
+    InFolderPrepend,
+    /// In the target folder (as last item)
+    InFolderAppend,
+}
+
+/// A `XbelPath` resolved as a folder (see `Xbel::resolve_folder_mut`)
+#[derive(Debug, PartialEq)]
+pub enum Resolved<'a> {
+    /// The folder children (or the root level items)
+    Folder(&'a mut Vec<XbelItem>),
+    /// The path resolved to a bookmark (which cannot contain any item)
+    Leaf(&'a mut Bookmark),
+    NotFound,
+}
+
+/// Xbel serialization options (see `Xbel::to_writer_with_options`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOptions {
+    /// Write the highestId comment (`<!--- highestId :N: ... -->`) required by Floccus
+    pub highest_id_comment: bool,
+    /// Write the DOCTYPE (external pyxml DTD reference, rejected by some strict xml parsers)
+    pub doctype: bool,
+    /// Number of spaces per nesting level
+    pub indent: usize,
+}
+
+impl Default for WriteOptions {
+    /// As written by Floccus (see `Xbel::to_string`)
+    fn default() -> Self {
+        Self {
+            highest_id_comment: true,
+            doctype: true,
+            indent: 2,
+        }
+    }
+}
+
+/// The parent of an item (see `Xbel::parent_of` and `Xbel::parent_of_mut`)
+#[derive(Debug, PartialEq)]
+pub enum Parent<F> {
+    /// The item is a top level item
+    Root,
+    /// The folder containing the item
+    Folder(F),
+    NotFound,
+}
+
+impl<F> Parent<F> {
+    /// The parent folder (None if the item is a top level item or not found)
+    pub fn folder(self) -> Option<F> {
+        match self {
+            Parent::Folder(f) => Some(f),
+            Parent::Root | Parent::NotFound => None,
+        }
+    }
 }
 
 /// A struct to search inside a `Xbel` struct
@@ -118,6 +383,51 @@ pub enum XbelPath {
     Path(String),
 }
 
+impl XbelPath {
+    /// Path separator (between folder titles) for `XbelPath::Path`
+    pub const SEPARATOR: char = '/';
+    /// Escape character: `\/` is a literal '/' (in a title) and `\\` a literal '\'
+    pub const ESCAPE: char = '\\';
+
+    /// Split a path (e.g. "news/A\/B") into titles (e.g. ["news", "A/B"])
+    pub fn split_path(path: &str) -> Vec<String> {
+        let mut titles = vec![];
+        let mut title = String::new();
+        let mut chars = path.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                Self::ESCAPE => match chars.next() {
+                    Some(escaped @ (Self::SEPARATOR | Self::ESCAPE)) => title.push(escaped),
+                    // Not an escape sequence - keep as is
+                    Some(c_next) => {
+                        title.push(c);
+                        title.push(c_next);
+                    }
+                    None => title.push(c),
+                },
+                Self::SEPARATOR => titles.push(std::mem::take(&mut title)),
+                _ => title.push(c),
+            }
+        }
+        titles.push(title);
+
+        titles
+    }
+
+    /// Escape a title so it can be used as a part of a path (see `XbelPath::split_path`)
+    pub fn escape_title(title: &str) -> String {
+        let mut escaped = String::with_capacity(title.len());
+        for c in title.chars() {
+            if c == Self::SEPARATOR || c == Self::ESCAPE {
+                escaped.push(Self::ESCAPE);
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+}
+
 impl Display for XbelPath {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -129,62 +439,327 @@ impl Display for XbelPath {
 }
 
 /// Struct resulting from parsing a Xbel file
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 #[serde(default, rename = "xbel")]
 pub struct Xbel {
     #[serde(rename = "@version")]
     version: String,
     #[serde(rename = "$value")]
     pub(crate) items: Vec<XbelItem>,
+    /// highestId as stored in the Xbel file comment (if any)
+    #[serde(skip)]
+    highest_id: Option<u64>,
+    /// Last id allocated by `next_id` (if any)
+    #[serde(skip)]
+    last_id: Option<u64>,
 }
 
 impl Xbel {
-    #[allow(dead_code)]
-    fn new(items: Option<Vec<XbelItem>>) -> Self {
+    /// Xbel version used for new Xbel (or if the version attribute is missing)
+    pub const VERSION_DEFAULT: &'static str = "1.0";
+
+    /// Create a new Xbel (with optional top level items)
+    ///
+    /// Item ids are not checked, see `Bookmark::new` and `Folder::new`.
+    pub fn new(items: Option<Vec<XbelItem>>) -> Self {
         Self {
-            version: "1.0".to_string(),
+            version: Self::VERSION_DEFAULT.to_string(),
             items: items.unwrap_or_default(),
+            highest_id: None,
+            last_id: None,
+        }
+    }
+
+    /// Parse a Xbel from a string (and read the highestId comment)
+    fn from_xml_str(content: &str) -> Result<Xbel, XbelError> {
+        // Skip a leading UTF-8 BOM (written by some tools) and whitespaces before the xml declaration
+        let content = content.trim_start_matches('\u{FEFF}').trim_start();
+        let mut xbel: Xbel = quick_xml::de::from_str(content)?;
+        xbel.highest_id = parse_highest_id(content);
+        Ok(xbel)
+    }
+
+    /// The Xbel version (as read from the version attribute, `VERSION_DEFAULT` if missing)
+    pub fn version(&self) -> &str {
+        if self.version.is_empty() {
+            Self::VERSION_DEFAULT
+        } else {
+            self.version.as_str()
+        }
+    }
+
+    /// Set the Xbel version (written in the version attribute)
+    pub fn set_version(&mut self, version: &str) {
+        self.version = version.to_string();
+    }
+
+    /// The highestId stored in the Xbel file comment (`<!--- highestId :N: ... -->`) if any
+    ///
+    /// This value is not updated when items are added or removed, see `get_highest_id`. It can
+    /// be higher than `get_highest_id` if the items with the highest ids have been removed.
+    pub fn stored_highest_id(&self) -> Option<u64> {
+        self.highest_id
+    }
+
+    /// The highest id of all Folder and Bookmark
+    ///
+    /// Missing (or non integer) ids are ignored.
+    pub fn get_highest_id(&self) -> u64 {
+        self.ids().max().unwrap_or_default()
+    }
+
+    /// Ids of all Folder and Bookmark (DFS order), see `XbelItem::numeric_id`
+    ///
+    /// Missing (or non integer) ids are skipped. Duplicate ids are yielded as many times as found.
+    pub fn ids(&self) -> impl Iterator<Item = u64> + '_ {
+        XbelIterator::new(self).filter_map(XbelItem::numeric_id)
+    }
+
+    /// Give a new id (see `next_id`) to all items (DFS order) without id (missing id attribute
+    /// in the Xbel file)
+    ///
+    /// Returns the number of ids assigned. Note: this is done automatically when writing (see
+    /// `to_writer`).
+    pub fn assign_missing_ids(&mut self) -> usize {
+        let first_id = self.highest_id_used() + 1;
+        let mut next_id = first_id;
+        self.for_each_mut(|item| {
+            if item.has_missing_id() {
+                let id = next_id.to_string();
+                match item {
+                    XbelItem::Folder(f) => f.id = id,
+                    XbelItem::Bookmark(b) => b.id = id,
+                    XbelItem::Alias { .. } => unreachable!("an alias has no id"),
+                }
+                next_id += 1;
+            }
+        });
+        if next_id > first_id {
+            self.last_id = Some(next_id - 1);
         }
+        (next_id - first_id) as usize
+    }
+
+    /// The highest id ever used: the highest id of all Folder and Bookmark, the stored highestId
+    /// or the last id allocated by `next_id` (so ids of removed items are not reused)
+    fn highest_id_used(&self) -> u64 {
+        self.get_highest_id()
+            .max(self.highest_id.unwrap_or_default())
+            .max(self.last_id.unwrap_or_default())
+    }
+
+    /// Allocate a new id (highest id used + 1)
+    ///
+    /// Each call returns a new id, even if the previously allocated ids are not used yet (e.g.
+    /// creating several bookmarks before adding them).
+    pub fn next_id(&mut self) -> u64 {
+        let id = self.highest_id_used() + 1;
+        self.last_id = Some(id);
+        id
     }
 
-    pub(crate) fn get_highest_id(&self) -> u64 {
-        let it = XbelIterator::new(self);
-        it.fold(0, |mut acc, x| {
-            let id = x.get_id().parse::<u64>().unwrap();
-            if id > acc {
-                acc = id;
+    /// Count bookmarks per url host (e.g. "www.rust-lang.org")
+    ///
+    /// Bookmarks with an url that cannot be parsed or without a host (e.g. "javascript:..." or
+    /// "file:///...") are skipped.
+    pub fn hosts(&self) -> HashMap<String, usize> {
+        let mut hosts = HashMap::new();
+        for item in XbelIterator::new(self) {
+            let Some(url) = item.get_url().and_then(|u| url::Url::parse(u).ok()) else {
+                continue;
+            };
+            if let Some(host) = url.host_str() {
+                *hosts.entry(host.to_string()).or_insert(0) += 1;
             }
-            acc
+        }
+        hosts
+    }
+
+    /// Maximum nesting depth: number of levels containing items
+    ///
+    /// An empty Xbel has depth 0, a flat list of bookmarks has depth 1 and a bookmark in folder
+    /// "admin/bank" has depth 3.
+    pub fn max_depth(&self) -> usize {
+        nesting_depth(XbelNestingIterator::new(self))
+    }
+
+    /// Count all folders and bookmarks: (folder count, bookmark count), aliases are not counted
+    pub fn count(&self) -> (usize, usize) {
+        XbelIterator::new(self).fold((0, 0), |(folders, bookmarks), item| match item {
+            XbelItem::Folder(_) => (folders + 1, bookmarks),
+            XbelItem::Bookmark(_) => (folders, bookmarks + 1),
+            XbelItem::Alias { .. } => (folders, bookmarks),
         })
     }
 
-    /// Returns the mutable list of XbelItem containing the searched item (`XbelPath`)
-    pub fn get_items_mut(&mut self, path: &XbelPath) -> Option<(usize, &mut Vec<XbelItem>)> {
+    /// Get a bookmark given its id (None if not found or if the id is a folder id)
+    pub fn get_bookmark(&self, id: u64) -> Option<&Bookmark> {
+        let (item_index, items) = self.get_items(&XbelPath::Id(id))?;
+        match &items[item_index] {
+            XbelItem::Bookmark(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Get a folder given its id (None if not found or if the id is a bookmark id)
+    pub fn get_folder(&self, id: u64) -> Option<&Folder> {
+        let (item_index, items) = self.get_items(&XbelPath::Id(id))?;
+        match &items[item_index] {
+            XbelItem::Folder(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// The item (bookmark or folder) referenced by an alias (`XbelItem::Alias`)
+    ///
+    /// Returns None if `alias` is not an alias or if the referenced id is not found.
+    pub fn resolve_alias(&self, alias: &XbelItem) -> Option<&XbelItem> {
+        let XbelItem::Alias { reference } = alias else {
+            return None;
+        };
+        let (item_index, items) = self.get_items(&XbelPath::Id(reference.trim().parse().ok()?))?;
+        Some(&items[item_index])
+    }
+
+    /// Get the folder containing an item given its id
+    pub fn parent_of(&self, id: u64) -> Parent<&Folder> {
+        fn find_in(items: &[XbelItem], id: u64) -> Option<&Folder> {
+            items.iter().find_map(|item| match item {
+                XbelItem::Folder(f) if f.items.iter().any(|i| has_id(i, id)) => Some(f),
+                XbelItem::Folder(f) => find_in(&f.items, id),
+                XbelItem::Bookmark(_) | XbelItem::Alias { .. } => None,
+            })
+        }
+
+        if self.items.iter().any(|i| has_id(i, id)) {
+            return Parent::Root;
+        }
+        find_in(&self.items, id).map_or(Parent::NotFound, Parent::Folder)
+    }
+
+    /// Get the (mutable) folder containing an item given its id (see `parent_of`)
+    pub fn parent_of_mut(&mut self, id: u64) -> Parent<&mut Folder> {
+        fn find_in(items: &mut [XbelItem], id: u64) -> Option<&mut Folder> {
+            for item in items.iter_mut() {
+                if let XbelItem::Folder(f) = item {
+                    if f.items.iter().any(|i| has_id(i, id)) {
+                        return Some(f);
+                    }
+                    if let Some(parent) = find_in(&mut f.items, id) {
+                        return Some(parent);
+                    }
+                }
+            }
+            None
+        }
+
+        if self.items.iter().any(|i| has_id(i, id)) {
+            return Parent::Root;
+        }
+        find_in(&mut self.items, id).map_or(Parent::NotFound, Parent::Folder)
+    }
+
+    /// Iterate over all bookmarks (DFS order)
+    pub fn bookmarks(&self) -> impl Iterator<Item = &Bookmark> {
+        XbelIterator::new(self).filter_map(|item| match item {
+            XbelItem::Bookmark(b) => Some(b),
+            _ => None,
+        })
+    }
+
+    /// Iterate over all folders (DFS order)
+    pub fn folders(&self) -> impl Iterator<Item = &Folder> {
+        XbelIterator::new(self).filter_map(|item| match item {
+            XbelItem::Folder(f) => Some(f),
+            _ => None,
+        })
+    }
+
+    /// Iterate mutably over all bookmarks (DFS order), e.g. for bulk edits of urls
+    pub fn bookmarks_mut(&mut self) -> XbelBookmarksMut<'_> {
+        XbelBookmarksMut {
+            to_process: vec![self.items.iter_mut()],
+        }
+    }
+
+    /// Rewrite bookmark urls (DFS order)
+    ///
+    /// `rewrite` returns the new url of a bookmark (or None to keep it). Returns the bookmarks
+    /// whose url has changed: (bookmark id, previous url).
+    pub fn rewrite_urls<F: FnMut(&str) -> Option<String>>(
+        &mut self,
+        mut rewrite: F,
+    ) -> Vec<(String, String)> {
+        let mut rewritten = vec![];
+        for bookmark in self.bookmarks_mut() {
+            if let Some(href) = rewrite(&bookmark.href) {
+                if href != bookmark.href {
+                    let previous = std::mem::replace(&mut bookmark.href, href);
+                    rewritten.push((bookmark.id.clone(), previous));
+                }
+            }
+        }
+        rewritten
+    }
+
+    /// Call `f` on all items (DFS order, a folder before its children)
+    ///
+    /// Note: there is no mutable iterator over all items as a folder and its children cannot
+    /// be borrowed mutably at the same time (see `bookmarks_mut` for bookmarks only). Children
+    /// are visited after `f` has been called on their folder (so any change is seen).
+    pub fn for_each_mut<F: FnMut(&mut XbelItem)>(&mut self, mut f: F) {
+        self.walk_mut(|item, _depth| f(item));
+    }
+
+    /// Call `f` on all items (DFS order) with their depth (0: top level)
+    ///
+    /// See `iter_with_depth` for an iterator.
+    pub fn walk<F: FnMut(&XbelItem, usize)>(&self, mut f: F) {
+        self.iter_with_depth()
+            .for_each(|(depth, item)| f(item, depth));
+    }
+
+    /// Call `f` on all items (DFS order, a folder before its children) with their depth
+    /// (0: top level)
+    ///
+    /// See `for_each_mut`.
+    pub fn walk_mut<F: FnMut(&mut XbelItem, usize)>(&mut self, mut f: F) {
+        fn visit<F: FnMut(&mut XbelItem, usize)>(items: &mut [XbelItem], depth: usize, f: &mut F) {
+            for item in items.iter_mut() {
+                f(item, depth);
+                if let XbelItem::Folder(folder) = item {
+                    visit(&mut folder.items, depth + 1, f);
+                }
+            }
+        }
+        visit(&mut self.items, 0, &mut f);
+    }
+
+    /// Iterate over all items (DFS) with their depth (0: top level)
+    pub fn iter_with_depth(&self) -> XbelDepthIterator<'_> {
+        XbelDepthIterator::new(self)
+    }
+
+    /// Returns the list of XbelItem containing the searched item (`XbelPath`)
+    ///
+    /// For `XbelPath::Path`, the path starts at root level and each title (except the last one)
+    /// must be a folder (e.g. "admin/bank": folder "bank" in root level folder "admin")
+    pub fn get_items(&self, path: &XbelPath) -> Option<(usize, &Vec<XbelItem>)> {
         match path {
-            XbelPath::Root => Some((0, &mut self.items)),
+            XbelPath::Root => Some((0, &self.items)),
             XbelPath::Id(id) => {
                 // All the Vec<XbelItem> to check (in order to find the id)
-                let mut to_process = VecDeque::from([&mut self.items]);
+                let mut to_process = VecDeque::from([&self.items]);
                 while let Some(items) = to_process.pop_front() {
-                    let found = items.iter().enumerate().find_map(|(item_index, item)| {
-                        let item_id = item.get_id().parse::<u64>().unwrap();
-                        if item_id == *id {
-                            Some(item_index)
-                        } else {
-                            None
-                        }
-                    });
+                    let found = items.iter().position(|item| item.numeric_id() == Some(*id));
                     if let Some(item_index) = found {
                         return Some((item_index, items));
                     }
 
                     // If not found yet, update to_process
-                    for item in items.iter_mut() {
-                        match item {
-                            XbelItem::Folder(ref mut f) => {
-                                to_process.push_back(&mut f.items);
-                            }
-                            XbelItem::Bookmark(_) => {}
+                    for item in items.iter() {
+                        if let XbelItem::Folder(f) = item {
+                            to_process.push_back(&f.items);
                         }
                     }
                 }
@@ -192,116 +767,784 @@ impl Xbel {
                 None
             }
             XbelPath::Path(s) => {
-                let path_split = s.split('/').collect::<Vec<&str>>();
-                // Safe to unwrap()
-                let mut path_split_index = 0;
+                let path_split = XbelPath::split_path(s);
+                // Safe to unwrap - split_path always returns at least one title
+                let (title, folder_titles) = path_split.split_last().unwrap();
+
+                // Descend (from root) in the folders matching the path
+                let mut items = &self.items;
+                for folder_title in folder_titles {
+                    items = items.iter().find_map(|item| match item {
+                        XbelItem::Folder(f) if f.title.text == *folder_title => Some(&f.items),
+                        _ => None,
+                    })?;
+                }
+
+                let item_index = items.iter().position(|item| {
+                    !matches!(item, XbelItem::Alias { .. }) && item.get_title().text == *title
+                })?;
+                Some((item_index, items))
+            }
+        }
+    }
 
+    /// Resolve a `XbelPath` as a folder (e.g. to add items to it)
+    ///
+    /// Unlike `Xbel::get_items_mut`, an item found but which is not a folder (`Resolved::Leaf`)
+    /// can be told apart from nothing found (`Resolved::NotFound`). `XbelPath::Root` resolves to
+    /// the root level items.
+    pub fn resolve_folder_mut(&mut self, path: &XbelPath) -> Resolved<'_> {
+        if let XbelPath::Root = path {
+            return Resolved::Folder(&mut self.items);
+        }
+        match self.get_items_mut(path) {
+            None => Resolved::NotFound,
+            Some((item_index, items)) => match &mut items[item_index] {
+                XbelItem::Folder(f) => Resolved::Folder(&mut f.items),
+                XbelItem::Bookmark(b) => Resolved::Leaf(b),
+                // Note: an alias has no id and no title (cannot be found by a XbelPath)
+                XbelItem::Alias { .. } => Resolved::NotFound,
+            },
+        }
+    }
+
+    /// Returns the mutable list of XbelItem containing the searched item (`XbelPath`)
+    ///
+    /// See `Xbel::get_items`
+    pub fn get_items_mut(&mut self, path: &XbelPath) -> Option<(usize, &mut Vec<XbelItem>)> {
+        match path {
+            XbelPath::Root => Some((0, &mut self.items)),
+            XbelPath::Id(id) => {
                 // All the Vec<XbelItem> to check (in order to find the id)
                 let mut to_process = VecDeque::from([&mut self.items]);
-
                 while let Some(items) = to_process.pop_front() {
                     let found = items.iter().enumerate().find_map(|(item_index, item)| {
-                        if item.get_title().text == path_split[path_split_index] {
+                        let item_id = item.numeric_id()?;
+                        if item_id == *id {
                             Some(item_index)
                         } else {
                             None
                         }
                     });
                     if let Some(item_index) = found {
-                        if path_split_index == path_split.len() - 1 {
-                            return Some((item_index, items));
-                        } else {
-                            path_split_index += 1;
-                        }
+                        return Some((item_index, items));
                     }
 
-                    // Not (all) found yet, update to_process
+                    // If not found yet, update to_process
                     for item in items.iter_mut() {
-                        match item {
-                            XbelItem::Folder(ref mut f) => {
-                                to_process.push_back(&mut f.items);
-                            }
-                            XbelItem::Bookmark(_) => {}
+                        if let XbelItem::Folder(ref mut f) = item {
+                            to_process.push_back(&mut f.items);
                         }
                     }
                 }
 
                 None
             }
+            XbelPath::Path(s) => {
+                let path_split = XbelPath::split_path(s);
+                // Safe to unwrap - split_path always returns at least one title
+                let (title, folder_titles) = path_split.split_last().unwrap();
+
+                // Descend (from root) in the folders matching the path
+                let mut items = &mut self.items;
+                for folder_title in folder_titles {
+                    items = items.iter_mut().find_map(|item| match item {
+                        XbelItem::Folder(f) if f.title.text == *folder_title => Some(&mut f.items),
+                        _ => None,
+                    })?;
+                }
+
+                let item_index = items.iter().position(|item| {
+                    !matches!(item, XbelItem::Alias { .. }) && item.get_title().text == *title
+                })?;
+                Some((item_index, items))
+            }
         }
     }
 
-    #[allow(clippy::inherent_to_string)]
-    /// Serialize to string
+    /// Normalize all folder and bookmark titles (see `Title::normalize`)
     ///
-    /// This is the recommended way to serialize a Xbel that will be compatible with Floccus. Using
-    /// the derive implementation should result in a valid xml file but missing some information
-    /// and proper indentation.
-    pub fn to_string(&self) -> String {
-        // Note:
-        // quick_xml 0.37 (when using the derive feature) can serialize comment (for highest_id)
-
-        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
-        let comment = format!(
-            "- highestId :{}: for Floccus bookmark sync browser extension ",
-            self.get_highest_id()
-        );
-        writer
-            .write_event(Event::Comment(BytesText::new(comment.as_str())))
-            .expect("writing comment should succeed");
-        writer
-            .write_event(Event::Text(BytesText::new("\n\n")))
-            .expect("writing empty should succeed");
+    /// Returns the number of titles changed
+    pub fn normalize_titles(&mut self) -> usize {
+        let mut normalized = 0;
+        self.for_each_mut(|item| {
+            if let Some(title) = item.get_title_mut() {
+                normalized += usize::from(title.normalize());
+            }
+        });
+        normalized
+    }
 
-        for item in self.items.iter() {
-            write_xbel_item(&mut writer, item);
+    /// All bookmarks (DFS order) with their folder path (e.g. "admin/bank", "" at root level)
+    ///
+    /// Folder titles are escaped (see `XbelPath::escape_title`) so a path can be used as a
+    /// `XbelPath::Path`.
+    pub fn flatten(&self) -> Vec<(String, &Bookmark)> {
+        fn flatten_in<'a>(
+            items: &'a [XbelItem],
+            path: &str,
+            res: &mut Vec<(String, &'a Bookmark)>,
+        ) {
+            for item in items {
+                match item {
+                    XbelItem::Bookmark(b) => res.push((path.to_string(), b)),
+                    XbelItem::Folder(f) => {
+                        let title = XbelPath::escape_title(&f.title.text);
+                        let folder_path = if path.is_empty() {
+                            title
+                        } else {
+                            format!("{}{}{}", path, XbelPath::SEPARATOR, title)
+                        };
+                        flatten_in(&f.items, folder_path.as_str(), res);
+                    }
+                    XbelItem::Alias { .. } => {}
+                }
+            }
         }
 
-        let result_ = writer.into_inner();
-
-        const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
-"#;
-        const XBEL_START: &str = "<xbel version=\"1.0\">\n";
-        const XBEL_END: &str = "\n</xbel>";
-
-        let mut result = String::with_capacity(
-            result_.len() + XML_HEADER.len() + XBEL_START.len() + XBEL_END.len(),
-        );
-
-        result.push_str(XML_HEADER);
-        result.push_str(XBEL_START);
-        result.push_str(String::from_utf8(result_).unwrap().as_str());
-        result.push_str(XBEL_END);
+        let mut res = vec![];
+        flatten_in(&self.items, "", &mut res);
+        res
+    }
 
-        result
+    /// A new Xbel with a copy of the item (and all its children if it's a folder) found at path
+    ///
+    /// The item is the only top level item of the new Xbel (or all items for
+    /// `XbelPath::Root`), item ids are kept. Returns None if nothing is found.
+    pub fn subtree(&self, path: &XbelPath) -> Option<Xbel> {
+        let items = match path {
+            XbelPath::Root => self.items.clone(),
+            _ => {
+                let (item_index, items) = self.get_items(path)?;
+                vec![items[item_index].clone()]
+            }
+        };
+        let mut xbel = Xbel::new(Some(items));
+        xbel.version = self.version.clone();
+        xbel.highest_id = Some(xbel.get_highest_id());
+        Some(xbel)
     }
 
-    /// Create a new bookmark for this Xbel using the correct id (highest id + 1).
-    pub fn new_bookmark(&self, url: &str, title: &str) -> XbelItem {
-        let highest_id = self.get_highest_id();
+    /// Returns the folders (from root level) containing the item with the given id
+    ///
+    /// An empty Vec means the item is at root level, None means not found
+    pub fn find_path_of(&self, id: u64) -> Option<Vec<&Folder>> {
+        fn find_in<'a>(items: &'a [XbelItem], id: &str, path: &mut Vec<&'a Folder>) -> bool {
+            items.iter().any(|item| {
+                if item.get_id() == id {
+                    return true;
+                }
+                match item {
+                    XbelItem::Folder(f) => {
+                        path.push(f);
+                        let found = find_in(&f.items, id, path);
+                        if !found {
+                            path.pop();
+                        }
+                        found
+                    }
+                    XbelItem::Bookmark(_) | XbelItem::Alias { .. } => false,
+                }
+            })
+        }
 
-        XbelItem::new_bookmark((highest_id + 1).to_string().as_str(), url, title)
+        let mut path = vec![];
+        find_in(&self.items, id.to_string().as_str(), &mut path).then_some(path)
     }
 
-    /// Parse a file into a Xbel
-    pub fn try_from_file<T: AsRef<Path>>(path: T) -> Result<Xbel, XbelError> {
-        let xbel_ = std::fs::File::open(path)?;
-        let xbel: Xbel = from_reader(BufReader::new(xbel_))?;
-        Ok(xbel)
+    /// Remove an item (a bookmark or a folder with all its children) given its id
+    ///
+    /// Returns the removed item or None if not found
+    pub fn remove_by_id(&mut self, id: u64) -> Option<XbelItem> {
+        let (item_index, items) = self.get_items_mut(&XbelPath::Id(id))?;
+        Some(items.remove(item_index))
     }
 
-    /// Write Xbel to a file
-    pub fn try_to_file<T: AsRef<Path>>(&self, file_path: T) -> Result<(), XbelError> {
-        let mut f = std::fs::File::options()
-            .write(true)
-            .truncate(true)
-            .open(file_path)?;
-        let buffer = self.to_string();
-        f.write_all(buffer.as_bytes())?;
-        Ok(())
+    /// Replace an item (a bookmark or a folder with all its children) given its id
+    ///
+    /// The new item takes the position of the replaced one. Returns the replaced item or None if
+    /// not found (nothing is replaced).
+    pub fn replace_subtree(&mut self, id: u64, new: XbelItem) -> Option<XbelItem> {
+        let (item_index, items) = self.get_items_mut(&XbelPath::Id(id))?;
+        Some(std::mem::replace(&mut items[item_index], new))
     }
-}
+
+    /// Remove all empty folders (recursively)
+    ///
+    /// Folders are removed bottom-up: a folder containing only empty folders is also removed.
+    /// Returns the ids of the removed folders (children before their parent)
+    pub fn prune_empty_folders(&mut self) -> Vec<String> {
+        let mut removed = vec![];
+        prune_empty_folders(&mut self.items, &mut removed);
+        removed
+    }
+
+    /// Move an item (a bookmark or a folder with all its children) given its id
+    ///
+    /// The item is inserted relatively to the target item (or in the target folder) according to
+    /// `placement`. `XbelPath::Root` can only be used with `Placement::InFolderPrepend` or
+    /// `Placement::InFolderAppend`.
+    pub fn move_item(
+        &mut self,
+        id: u64,
+        target: &XbelPath,
+        placement: Placement,
+    ) -> Result<(), XbelMoveError> {
+        let (item_index, items) = self
+            .get_items(&XbelPath::Id(id))
+            .ok_or(XbelMoveError::NotFound(id))?;
+        let item = &items[item_index];
+        let target_item = self.check_target(target, &placement)?;
+
+        // Cannot move an item relatively to itself or into one of its children
+        if let Some(target_item) = target_item {
+            let target_id = target_item.get_id();
+            if XbelIterator::from_item(item).any(|i| i.get_id() == target_id) {
+                return Err(XbelMoveError::Cycle(id, target_id.clone()));
+            }
+        }
+
+        // Detach then insert (Note: target is searched again as indexes may have changed)
+        // Safe to unwrap - item and target checked above
+        let item = self.remove_by_id(id).unwrap();
+        self.insert_at_path(target, item, placement).unwrap();
+        Ok(())
+    }
+
+    /// Copy an item (a bookmark or a folder with all its children) given its id
+    ///
+    /// The copy (and all its children) gets new ids (starting from the highest id + 1, as
+    /// `merge`) and is inserted as `move_item` does. Unlike `move_item`, a folder can be copied
+    /// into itself (or into one of its children).
+    ///
+    /// Returns the mapping: copied item id -> new id
+    pub fn copy_item(
+        &mut self,
+        id: u64,
+        target: &XbelPath,
+        placement: Placement,
+    ) -> Result<HashMap<String, String>, XbelMoveError> {
+        let (item_index, items) = self
+            .get_items(&XbelPath::Id(id))
+            .ok_or(XbelMoveError::NotFound(id))?;
+        let mut item = items[item_index].clone();
+        self.check_target(target, &placement)?;
+
+        let mut next_id = self.highest_id_used() + 1;
+        let mut mapping = HashMap::new();
+        renumber(&mut item, &mut next_id, &mut mapping);
+        // Safe to unwrap - target checked above
+        self.insert_at_path(target, item, placement).unwrap();
+        Ok(mapping)
+    }
+
+    /// The target item (None for `XbelPath::Root`) if an item can be inserted according to
+    /// `placement` (see `move_item`)
+    fn check_target(
+        &self,
+        target: &XbelPath,
+        placement: &Placement,
+    ) -> Result<Option<&XbelItem>, XbelMoveError> {
+        let target_item = match target {
+            XbelPath::Root => None,
+            _ => {
+                let (target_index, target_items) = self
+                    .get_items(target)
+                    .ok_or_else(|| XbelMoveError::TargetNotFound(target.clone()))?;
+                Some(&target_items[target_index])
+            }
+        };
+
+        match (target_item, placement) {
+            (None, Placement::Before | Placement::After) => {
+                Err(XbelMoveError::InvalidPlacement(target.clone()))
+            }
+            (
+                Some(XbelItem::Bookmark(b)),
+                Placement::InFolderPrepend | Placement::InFolderAppend,
+            ) => Err(XbelMoveError::TargetNotAFolder(b.id.clone())),
+            _ => Ok(target_item),
+        }
+    }
+
+    /// Insert an item relatively to the item (or in the folder) found at path
+    ///
+    /// See `Xbel::insert_all_at_path`
+    pub fn insert_at_path(
+        &mut self,
+        path: &XbelPath,
+        item: XbelItem,
+        placement: Placement,
+    ) -> Result<(), XbelInsertError> {
+        self.insert_all_at_path(path, vec![item], placement)
+    }
+
+    /// Insert items (in order) relatively to the item (or in the folder) found at path
+    ///
+    /// `Placement::Before` or `Placement::After` an item (a folder or a bookmark), or
+    /// `Placement::InFolderPrepend` or `Placement::InFolderAppend` for a folder. `XbelPath::Root`
+    /// (root level items) can only be used with `Placement::InFolderPrepend` or
+    /// `Placement::InFolderAppend`. Nothing is inserted on error.
+    pub fn insert_all_at_path(
+        &mut self,
+        path: &XbelPath,
+        items: Vec<XbelItem>,
+        placement: Placement,
+    ) -> Result<(), XbelInsertError> {
+        let (index, target_items) = match (path, placement) {
+            (XbelPath::Root, Placement::Before | Placement::After) => {
+                return Err(XbelInsertError::InvalidPlacement(path.clone()))
+            }
+            (_, placement @ (Placement::Before | Placement::After)) => {
+                let (item_index, target_items) = self
+                    .get_items_mut(path)
+                    .ok_or_else(|| XbelInsertError::NotFound(path.clone()))?;
+                match placement {
+                    Placement::After => (item_index + 1, target_items),
+                    _ => (item_index, target_items),
+                }
+            }
+            (_, placement) => {
+                let target_items = match self.resolve_folder_mut(path) {
+                    Resolved::Folder(target_items) => target_items,
+                    Resolved::Leaf(b) => return Err(XbelInsertError::NotaFolder(b.id.clone())),
+                    Resolved::NotFound => return Err(XbelInsertError::NotFound(path.clone())),
+                };
+                match placement {
+                    Placement::InFolderPrepend => (0, target_items),
+                    _ => (target_items.len(), target_items),
+                }
+            }
+        };
+        target_items.splice(index..index, items);
+        Ok(())
+    }
+
+    #[allow(clippy::inherent_to_string)]
+    /// Serialize to string
+    ///
+    /// This is the recommended way to serialize a Xbel that will be compatible with Floccus. Using
+    /// the derive implementation should result in a valid xml file but missing some information
+    /// and proper indentation.
+    ///
+    /// Elements are never self-closing (as written by Floccus): a folder without children is
+    /// written as `<folder id=".."><title>..</title></folder>` and an empty title as
+    /// `<title></title>`. The reader accepts both forms (and a missing title).
+    ///
+    /// Panics if serialization fails, see `try_to_string` or `to_writer` for a fallible version.
+    pub fn to_string(&self) -> String {
+        self.try_to_string()
+            .expect("serializing Xbel to string should succeed")
+    }
+
+    /// Serialize to string (see `to_string`)
+    pub fn try_to_string(&self) -> Result<String, XbelError> {
+        let mut buffer = Vec::new();
+        self.to_writer(&mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| XbelError::Encoding(e.utf8_error()))
+    }
+
+    /// Serialize to string with the given options (see `WriteOptions`, fallible unlike `to_string`)
+    pub fn to_string_with_options(&self, options: &WriteOptions) -> Result<String, XbelError> {
+        let mut buffer = Vec::new();
+        self.to_writer_with_options(&mut buffer, options)?;
+        String::from_utf8(buffer).map_err(|e| XbelError::Encoding(e.utf8_error()))
+    }
+
+    /// Serialize to a writer (see `to_string`)
+    ///
+    /// Items without id are written with a new id (see `assign_missing_ids`), self is unchanged.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), XbelError> {
+        self.to_writer_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Serialize to a writer with the given options (see `to_writer` and `WriteOptions`)
+    pub fn to_writer_with_options<W: Write>(
+        &self,
+        mut writer: W,
+        options: &WriteOptions,
+    ) -> Result<(), XbelError> {
+        if XbelIterator::new(self).any(|item| item.has_missing_id()) {
+            let mut xbel = self.clone();
+            xbel.assign_missing_ids();
+            return xbel.to_writer_with_options(writer, options);
+        }
+
+        // Note:
+        // quick_xml 0.37 (when using the derive feature) can serialize comment (for highest_id)
+
+        let mut xml_writer = Writer::new_with_indent(Vec::new(), b' ', options.indent);
+        if options.highest_id_comment {
+            let comment = format!(
+                "- highestId :{}: for Floccus bookmark sync browser extension ",
+                self.highest_id_used()
+            );
+            xml_writer.write_event(Event::Comment(BytesText::new(comment.as_str())))?;
+            xml_writer.write_event(Event::Text(BytesText::new("\n\n")))?;
+        }
+
+        for item in self.items.iter() {
+            write_xbel_item(&mut xml_writer, item)?;
+        }
+
+        let result_ = xml_writer.into_inner();
+        // Only write valid UTF-8 (as declared in the xml header)
+        let result_ = std::str::from_utf8(result_.as_slice())?;
+
+        const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+        const XML_DOCTYPE: &str = r#"<!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+"#;
+        const XBEL_END: &str = "\n</xbel>";
+
+        writer.write_all(XML_HEADER.as_bytes())?;
+        if options.doctype {
+            writer.write_all(XML_DOCTYPE.as_bytes())?;
+        }
+        writeln!(
+            writer,
+            "<xbel version=\"{}\">",
+            quick_xml::escape::escape(self.version())
+        )?;
+        writer.write_all(result_.as_bytes())?;
+        writer.write_all(XBEL_END.as_bytes())?;
+        Ok(())
+    }
+
+    /// Create a new bookmark for this Xbel using the correct id (see `next_id`).
+    ///
+    /// The highest id is the highest of all Folder and Bookmark ids or the stored highestId if
+    /// higher (ids of removed items are never reused).
+    pub fn new_bookmark(&mut self, url: &str, title: &str) -> XbelItem {
+        XbelItem::new_bookmark(self.next_id().to_string().as_str(), url, title)
+    }
+
+    /// Create a new (empty) folder for this Xbel using the correct id (see `next_id`).
+    pub fn new_folder(&mut self, title: &str) -> XbelItem {
+        XbelItem::new_folder(self.next_id().to_string().as_str(), title, None)
+    }
+
+    /// Create the missing folders of a folder titles path (e.g. "news/tech/rust"), like `mkdir -p`
+    ///
+    /// Every title of the path is a folder (existing folders are matched as in
+    /// `Xbel::get_items`). Missing folders are appended to their parent folder and get
+    /// consecutive ids (see `next_id`) from the outermost to the innermost one. Returns the ids of
+    /// the created folders (in this order, empty if the whole path already exists).
+    pub fn create_folder_path(&mut self, path: &str) -> Vec<u64> {
+        let titles = XbelPath::split_path(path);
+
+        // Number of folders (from root level) already existing
+        let mut existing = 0;
+        let mut items = &self.items;
+        for title in titles.iter() {
+            match items.iter().find_map(|item| match item {
+                XbelItem::Folder(f) if f.title.text == *title => Some(&f.items),
+                _ => None,
+            }) {
+                Some(folder_items) => {
+                    items = folder_items;
+                    existing += 1;
+                }
+                None => break,
+            }
+        }
+
+        let ids = titles[existing..]
+            .iter()
+            .map(|_title| self.next_id())
+            .collect::<Vec<_>>();
+        // Build the chain of missing folders (innermost first)
+        let chain = titles[existing..].iter().zip(ids.iter()).rev().fold(
+            None,
+            |child: Option<XbelItem>, (title, id)| {
+                Some(XbelItem::new_folder(
+                    id.to_string().as_str(),
+                    title,
+                    child.map(|c| vec![c]),
+                ))
+            },
+        );
+
+        if let Some(chain) = chain {
+            let mut items = &mut self.items;
+            for title in titles[..existing].iter() {
+                // Safe to unwrap - the folder has been found above
+                items = items
+                    .iter_mut()
+                    .find_map(|item| match item {
+                        XbelItem::Folder(f) if f.title.text == *title => Some(&mut f.items),
+                        _ => None,
+                    })
+                    .unwrap();
+            }
+            items.push(chain);
+        }
+
+        ids
+    }
+
+    /// Create new bookmarks for this Xbel using consecutive ids (see `next_id`).
+    pub fn new_bookmarks<'a>(
+        &mut self,
+        bookmarks: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Vec<XbelItem> {
+        bookmarks
+            .into_iter()
+            .map(|(url, title)| self.new_bookmark(url, title))
+            .collect()
+    }
+
+    /// Merge another Xbel into this one
+    ///
+    /// All items of `other` are appended at root level with new ids (starting from the highest id
+    /// of this Xbel + 1). If `fuse_same_title` is true, folders with the same title at the same
+    /// level are fused into one folder (keeping the id of the folder in this Xbel).
+    ///
+    /// Returns the mapping: id in `other` -> new id
+    ///
+    /// Note: alias references (`XbelItem::Alias`) of `other` are kept as is (not updated with the
+    /// new ids).
+    pub fn merge(&mut self, other: Xbel, fuse_same_title: bool) -> HashMap<String, String> {
+        let mut next_id = self.highest_id_used() + 1;
+        let mut mapping = HashMap::new();
+        merge_items(
+            &mut self.items,
+            other.items,
+            fuse_same_title,
+            &mut next_id,
+            &mut mapping,
+        );
+        mapping
+    }
+
+    /// Parse a file into a Xbel
+    pub fn try_from_file<T: AsRef<Path>>(path: T) -> Result<Xbel, XbelError> {
+        let xbel_ = std::fs::File::open(path)?;
+        Self::try_from_reader(BufReader::new(xbel_))
+    }
+
+    /// Parse a Xbel from a reader (e.g. an in memory buffer or a network stream)
+    ///
+    /// Gzip compressed content (e.g. a .xbel.gz file) is decompressed. Content with git conflict
+    /// markers (left by a git merge) is rejected (`XbelError::MergeConflict`).
+    pub fn try_from_reader<R: Read>(mut reader: R) -> Result<Xbel, XbelError> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        let content = if content.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+            decompressed
+        } else {
+            content
+        };
+        if let Some(line) = find_conflict_marker(&content) {
+            return Err(XbelError::MergeConflict(line));
+        }
+        let content = String::from_utf8(content)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Self::from_xml_str(content.as_str())
+    }
+
+    /// Write Xbel to a file (gzip compressed if the file extension is .gz)
+    ///
+    /// The content is first written to a temporary file (in the same folder) which then replaces
+    /// the file (atomic rename), so the file is never left truncated or half written on error.
+    /// The file is created if it does not exist.
+    pub fn try_to_file<T: AsRef<Path>>(&self, file_path: T) -> Result<(), XbelError> {
+        let file_path = file_path.as_ref();
+        let folder = match file_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut tmp_file = tempfile::NamedTempFile::new_in(folder)?;
+        if Self::is_gzip_path(file_path) {
+            self.to_gzip_writer(&mut tmp_file)?;
+        } else {
+            self.to_writer(&mut tmp_file)?;
+        }
+        // Keep the permissions of the replaced file (a temporary file is only readable by its owner)
+        if let Ok(metadata) = std::fs::metadata(file_path) {
+            tmp_file.as_file().set_permissions(metadata.permissions())?;
+        }
+        tmp_file.as_file().sync_all()?;
+        tmp_file.persist(file_path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Write Xbel (gzip compressed) to a writer
+    pub fn to_gzip_writer<W: Write>(&self, writer: W) -> Result<(), XbelError> {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        self.to_writer(&mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Serialize to a markdown nested bullet list
+    ///
+    /// Folders are bold bullets (`- **title**`), bookmarks are links (`- [title](href)`, the url is
+    /// used if the title is empty) and each level is indented by 2 spaces. Markdown characters in
+    /// titles are escaped with a backslash.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        for (depth, item) in XbelDepthIterator::new(self) {
+            let indent = "  ".repeat(depth);
+            let line = match item {
+                XbelItem::Folder(f) => format!("**{}**", markdown_escape(&f.title.text)),
+                XbelItem::Bookmark(b) => {
+                    let title = if b.title.text.is_empty() {
+                        &b.href
+                    } else {
+                        &b.title.text
+                    };
+                    // Note: spaces and parentheses would end the link destination
+                    let href = b
+                        .href
+                        .replace(' ', "%20")
+                        .replace('(', "%28")
+                        .replace(')', "%29");
+                    format!("[{}]({})", markdown_escape(title), href)
+                }
+                // Note: markdown has no reference to another list item
+                XbelItem::Alias { .. } => continue,
+            };
+            markdown.push_str(&format!("{}- {}\n", indent, line));
+        }
+        markdown
+    }
+
+    /// A file path with a .gz extension (e.g. bookmarks.xbel.gz)
+    pub fn is_gzip_path<T: AsRef<Path>>(path: T) -> bool {
+        path.as_ref().extension().is_some_and(|ext| ext == "gz")
+    }
+}
+
+/// Gzip header magic bytes
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Line number (starting from 1) of the first git conflict marker (`<<<<<<<`, `=======` or
+/// `>>>>>>>` at the start of a line) if any
+fn find_conflict_marker(content: &[u8]) -> Option<usize> {
+    content
+        .split(|b| *b == b'\n')
+        .position(|line| {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            line.starts_with(b"<<<<<<<") || line.starts_with(b">>>>>>>") || line == b"======="
+        })
+        .map(|index| index + 1)
+}
+
+/// Compare 2 strings (case-insensitively first if `case_insensitive` is true)
+fn cmp_text(text: &str, other: &str, case_insensitive: bool) -> Ordering {
+    let ordering = if case_insensitive {
+        text.to_lowercase().cmp(&other.to_lowercase())
+    } else {
+        Ordering::Equal
+    };
+    ordering.then_with(|| text.cmp(other))
+}
+
+fn prune_empty_folders(items: &mut Vec<XbelItem>, removed: &mut Vec<String>) {
+    items.retain_mut(|item| match item {
+        XbelItem::Folder(f) => {
+            prune_empty_folders(&mut f.items, removed);
+            if f.is_empty() {
+                removed.push(f.id.clone());
+            }
+            !f.is_empty()
+        }
+        XbelItem::Bookmark(_) | XbelItem::Alias { .. } => true,
+    });
+}
+
+fn merge_items(
+    items: &mut Vec<XbelItem>,
+    others: Vec<XbelItem>,
+    fuse_same_title: bool,
+    next_id: &mut u64,
+    mapping: &mut HashMap<String, String>,
+) {
+    for mut other in others {
+        if let (true, XbelItem::Folder(other_f)) = (fuse_same_title, &mut other) {
+            let same_title = items
+                .iter()
+                .position(|i| matches!(i, XbelItem::Folder(f) if f.title == other_f.title));
+            if let Some(index) = same_title {
+                if let XbelItem::Folder(f) = &mut items[index] {
+                    mapping.insert(other_f.id.clone(), f.id.clone());
+                    let other_items = std::mem::take(&mut other_f.items);
+                    merge_items(&mut f.items, other_items, fuse_same_title, next_id, mapping);
+                    continue;
+                }
+            }
+        }
+
+        renumber(&mut other, next_id, mapping);
+        items.push(other);
+    }
+}
+
+/// Assign new ids (starting at `next_id`) to an item and all its children (if it's a folder)
+fn renumber(item: &mut XbelItem, next_id: &mut u64, mapping: &mut HashMap<String, String>) {
+    // Note: an alias has no id (and its reference is not updated, see `Xbel::merge`)
+    if let XbelItem::Alias { .. } = item {
+        return;
+    }
+    let new_id = next_id.to_string();
+    *next_id += 1;
+    match item {
+        XbelItem::Folder(f) => {
+            mapping.insert(std::mem::replace(&mut f.id, new_id.clone()), new_id);
+            for child in f.items.iter_mut() {
+                renumber(child, next_id, mapping);
+            }
+        }
+        XbelItem::Bookmark(b) => {
+            mapping.insert(std::mem::replace(&mut b.id, new_id.clone()), new_id);
+        }
+        XbelItem::Alias { .. } => {}
+    }
+}
+
+/// The item id is `id` (items with a non numeric id never match)
+fn has_id(item: &XbelItem, id: u64) -> bool {
+    item.numeric_id() == Some(id)
+}
+
+/// Escape (with a backslash) the characters with a meaning in markdown inline text
+fn markdown_escape(text: &str) -> String {
+    const SPECIAL_CHARS: &[char] = &['\\', '`', '*', '_', '[', ']', '<', '>', '#', '|', '~'];
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if SPECIAL_CHARS.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Read the highestId value in the Xbel comment: `<!--- highestId :N: for Floccus ... -->`
+fn parse_highest_id(content: &str) -> Option<u64> {
+    const HIGHEST_ID_PREFIX: &str = "highestId :";
+
+    let mut reader = Reader::from_str(content);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Comment(comment)) => {
+                let comment = comment.unescape().ok()?;
+                if let Some((_, rem)) = comment.split_once(HIGHEST_ID_PREFIX) {
+                    let (highest_id, _) = rem.split_once(':')?;
+                    return highest_id.trim().parse::<u64>().ok();
+                }
+            }
+            // Comment is expected before any folder or bookmark (root element could be <XBEL>)
+            Ok(Event::Start(e)) if !e.local_name().as_ref().eq_ignore_ascii_case(b"xbel") => {
+                return None
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum XbelError {
@@ -309,53 +1552,93 @@ pub enum XbelError {
     IoError(#[from] std::io::Error),
     #[error("Cannot parse Xbel file: {0}")]
     XbelReadError(#[from] quick_xml::de::DeError),
+    #[error("Invalid UTF-8 while serializing Xbel: {0}")]
+    Encoding(#[from] std::str::Utf8Error),
+    #[error("Cannot read Xbel file: {0}")]
+    XmlError(#[from] quick_xml::Error),
+    #[error("Xbel file has unresolved merge conflicts (conflict marker at line {0}) - please resolve them (git status) then commit")]
+    MergeConflict(usize),
+}
+
+#[derive(Error, Debug)]
+pub enum XbelInsertError {
+    #[error("Cannot find anything in Xbel matching: {0}")]
+    NotFound(XbelPath),
+    #[error("Item found with id: {0} but it is not a folder")]
+    NotaFolder(String),
+    #[error("Cannot insert before or after: {0}")]
+    InvalidPlacement(XbelPath),
+}
+
+#[derive(Error, Debug)]
+pub enum XbelMoveError {
+    #[error("Cannot find item with id: {0}")]
+    NotFound(u64),
+    #[error("Cannot find anything in Xbel matching: {0}")]
+    TargetNotFound(XbelPath),
+    #[error("Item found with id: {0} but it is not a folder")]
+    TargetNotAFolder(String),
+    #[error("Cannot move before or after: {0}")]
+    InvalidPlacement(XbelPath),
+    #[error(
+        "Cannot move item with id: {0} relatively to itself or to one of its children (id: {1})"
+    )]
+    Cycle(u64, String),
+}
+
+/// Write a title element, always as `<title>..</title>` on a single line (even if empty)
+fn write_title<W: std::io::Write>(writer: &mut Writer<W>, title: &Title) -> std::io::Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("title")))?;
+    // Note: always write the text event (even if empty) otherwise the indent writer puts the end
+    // tag on its own line
+    writer.write_event(Event::Text(BytesText::new(title.text.as_str())))?;
+    writer.write_event(Event::End(BytesEnd::new("title")))
 }
 
-fn write_xbel_item<W: std::io::Write>(writer: &mut Writer<W>, item: &XbelItem) {
+fn write_xbel_item<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    item: &XbelItem,
+) -> std::io::Result<()> {
     match item {
         XbelItem::Folder(f) => {
-            writer
-                .write_event(Event::Start(
-                    BytesStart::new("folder").with_attributes([("id", f.id.to_string().as_str())]),
-                ))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::Start(BytesStart::new("title")))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::Text(BytesText::new(f.title.text.as_str())))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::End(BytesEnd::new("title")))
-                .expect("writing start tag should succeed");
+            writer.write_event(Event::Start(
+                BytesStart::new("folder").with_attributes([("id", f.id.to_string().as_str())]),
+            ))?;
+            write_title(writer, &f.title)?;
             for it in f.items.iter() {
-                write_xbel_item(writer, it)
+                write_xbel_item(writer, it)?;
             }
-            writer
-                .write_event(Event::End(BytesEnd::new("folder")))
-                .expect("writing start tag should succeed");
+            writer.write_event(Event::End(BytesEnd::new("folder")))?;
         }
         XbelItem::Bookmark(b) => {
-            writer
-                .write_event(Event::Start(
-                    BytesStart::new("bookmark")
-                        .with_attributes([("href", b.href.as_str()), ("id", b.id.as_str())]),
-                ))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::Start(BytesStart::new("title")))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::Text(BytesText::new(b.title.text.as_str())))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::End(BytesEnd::new("title")))
-                .expect("writing start tag should succeed");
-            writer
-                .write_event(Event::End(BytesEnd::new("bookmark")))
-                .expect("writing start tag should succeed");
+            let mut bookmark_start = BytesStart::new("bookmark")
+                .with_attributes([("href", b.href.as_str()), ("id", b.id.as_str())]);
+            if let Some(added) = b.added.as_ref() {
+                bookmark_start.push_attribute(("added", added.as_str()));
+            }
+            if let Some(tags) = b.tags.as_ref() {
+                bookmark_start.push_attribute(("tags", tags.as_str()));
+            }
+            writer.write_event(Event::Start(bookmark_start))?;
+            write_title(writer, &b.title)?;
+            writer.write_event(Event::End(BytesEnd::new("bookmark")))?;
+        }
+        XbelItem::Alias { reference } => {
+            writer.write_event(Event::Empty(
+                BytesStart::new("alias").with_attributes([("ref", reference.as_str())]),
+            ))?;
         }
     }
+    Ok(())
+}
+
+impl FromStr for Xbel {
+    type Err = XbelError;
+
+    /// Parse a string into a Xbel (see `Xbel::try_from_reader`)
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        Self::try_from_reader(content.as_bytes())
+    }
 }
 
 impl<'a> IntoIterator for &'a Xbel {
@@ -369,7 +1652,7 @@ impl<'a> IntoIterator for &'a Xbel {
 
 /// A DFS Iterator for Xbel
 pub struct XbelIterator<'s> {
-    xbel: &'s Xbel,
+    items: &'s [XbelItem],
     initial: bool,
     to_process: VecDeque<&'s XbelItem>,
 }
@@ -377,7 +1660,16 @@ pub struct XbelIterator<'s> {
 impl<'s> XbelIterator<'s> {
     fn new(xbel: &'s Xbel) -> Self {
         Self {
-            xbel,
+            items: xbel.items.as_slice(),
+            initial: true,
+            to_process: Default::default(),
+        }
+    }
+
+    /// Iterate over an item (and its children if it's a folder)
+    pub fn from_item(item: &'s XbelItem) -> Self {
+        Self {
+            items: std::slice::from_ref(item),
             initial: true,
             to_process: Default::default(),
         }
@@ -389,7 +1681,7 @@ impl<'a> Iterator for XbelIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.initial {
-            self.to_process.extend(self.xbel.items.iter());
+            self.to_process.extend(self.items.iter());
             self.initial = false;
         }
 
@@ -408,13 +1700,16 @@ impl<'a> Iterator for XbelIterator<'a> {
 #[derive(Debug)]
 pub enum XbelItemOrEnd<'s> {
     Item(&'s XbelItem),
-    #[allow(dead_code)]
-    End(String), // id
+    /// End of the folder with this id and title
+    End {
+        id: String,
+        title: String,
+    },
 }
 
 /// A DFS Iterator for Xbel (but with nesting information)
 pub struct XbelNestingIterator<'s> {
-    xbel: &'s Xbel,
+    items: &'s [XbelItem],
     initial: bool,
     to_process: VecDeque<XbelItemOrEnd<'s>>,
 }
@@ -422,7 +1717,16 @@ pub struct XbelNestingIterator<'s> {
 impl<'s> XbelNestingIterator<'s> {
     pub fn new(xbel: &'s Xbel) -> Self {
         Self {
-            xbel,
+            items: xbel.items.as_slice(),
+            initial: true,
+            to_process: Default::default(),
+        }
+    }
+
+    /// Iterate over an item (and its children if it's a folder)
+    pub fn from_item(item: &'s XbelItem) -> Self {
+        Self {
+            items: std::slice::from_ref(item),
             initial: true,
             to_process: Default::default(),
         }
@@ -435,13 +1739,16 @@ impl<'a> Iterator for XbelNestingIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         if self.initial {
             self.to_process
-                .extend(self.xbel.items.iter().map(XbelItemOrEnd::Item));
+                .extend(self.items.iter().map(XbelItemOrEnd::Item));
             self.initial = false;
         }
 
         let xbel_iter_item = self.to_process.pop_front()?;
         if let XbelItemOrEnd::Item(XbelItem::Folder(f)) = xbel_iter_item {
-            self.to_process.push_front(XbelItemOrEnd::End(f.id.clone()));
+            self.to_process.push_front(XbelItemOrEnd::End {
+                id: f.id.clone(),
+                title: f.title.text.clone(),
+            });
             for i in f.items.iter().rev() {
                 self.to_process.push_front(XbelItemOrEnd::Item(i));
             }
@@ -451,6 +1758,91 @@ impl<'a> Iterator for XbelNestingIterator<'a> {
     }
 }
 
+/// Number of levels containing items (see `Xbel::max_depth`)
+fn nesting_depth(nesting_iter: XbelNestingIterator) -> usize {
+    let mut depth: usize = 0;
+    let mut max_depth = 0;
+    for item in nesting_iter {
+        match item {
+            // Note: saturating_sub so an unbalanced End cannot underflow
+            XbelItemOrEnd::End { .. } => depth = depth.saturating_sub(1),
+            XbelItemOrEnd::Item(item) => {
+                max_depth = max_depth.max(depth + 1);
+                if let XbelItem::Folder(_) = item {
+                    depth += 1;
+                }
+            }
+        }
+    }
+    max_depth
+}
+
+/// A DFS mutable Iterator over the bookmarks of a Xbel
+pub struct XbelBookmarksMut<'s> {
+    /// Remaining items of the folders currently open (+ top level)
+    to_process: Vec<std::slice::IterMut<'s, XbelItem>>,
+}
+
+impl<'a> Iterator for XbelBookmarksMut<'a> {
+    type Item = &'a mut Bookmark;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.to_process.last_mut()?.next() {
+                None => {
+                    self.to_process.pop();
+                }
+                Some(XbelItem::Folder(f)) => self.to_process.push(f.items.iter_mut()),
+                Some(XbelItem::Bookmark(b)) => return Some(b),
+                Some(XbelItem::Alias { .. }) => {}
+            }
+        }
+    }
+}
+
+/// A DFS Iterator for Xbel yielding the depth (0: top level) of each item
+pub struct XbelDepthIterator<'s> {
+    nesting_iter: XbelNestingIterator<'s>,
+    depth: usize,
+}
+
+impl<'s> XbelDepthIterator<'s> {
+    pub fn new(xbel: &'s Xbel) -> Self {
+        Self {
+            nesting_iter: XbelNestingIterator::new(xbel),
+            depth: 0,
+        }
+    }
+
+    /// Iterate over an item (depth 0) and its children if it's a folder
+    pub fn from_item(item: &'s XbelItem) -> Self {
+        Self {
+            nesting_iter: XbelNestingIterator::from_item(item),
+            depth: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for XbelDepthIterator<'a> {
+    type Item = (usize, &'a XbelItem);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.nesting_iter.next()? {
+                // Note: saturating_sub so an unbalanced End cannot underflow
+                XbelItemOrEnd::End { .. } => self.depth = self.depth.saturating_sub(1),
+                XbelItemOrEnd::Item(item) => {
+                    let depth = self.depth;
+                    if let XbelItem::Folder(_) = item {
+                        self.depth += 1;
+                    }
+                    return Some((depth, item));
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -488,43 +1880,54 @@ mod tests {
             </xbel>
         "#;
 
-    // XXX:
-    // Allow Clone for Folder/Bookmark (not only for tests)?
-    impl Clone for Title {
-        fn clone(&self) -> Self {
-            Self {
-                text: self.text.clone(),
-            }
-        }
-    }
+    #[test]
+    fn max_depth_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_EMPTY)?;
+        assert_eq!(xbel.max_depth(), 0);
 
-    impl Clone for Bookmark {
-        fn clone(&self) -> Self {
-            Self {
-                href: self.href.clone(),
-                id: self.id.clone(),
-                title: self.title.clone(),
-            }
-        }
-    }
+        // Flat list of bookmarks
+        let xbel = Xbel::new(Some(vec![
+            XbelItem::Bookmark(Bookmark::new("1", "https://a.com", "a")),
+            XbelItem::Bookmark(Bookmark::new("2", "https://b.com", "b")),
+        ]));
+        assert_eq!(xbel.max_depth(), 1);
 
-    impl Clone for XbelItem {
-        fn clone(&self) -> Self {
-            match self {
-                XbelItem::Folder(f) => XbelItem::Folder(f.clone()),
-                XbelItem::Bookmark(b) => XbelItem::Bookmark(b.clone()),
-            }
+        // Bookmarks in admin/bank
+        let mut xbel: Xbel = from_str(XBEL_BANK)?;
+        assert_eq!(xbel.max_depth(), 3);
+        assert_eq!(xbel.get_folder(1).unwrap().max_depth(), 2);
+        assert_eq!(xbel.get_folder(2).unwrap().max_depth(), 1);
+
+        // An empty folder is a level (but has depth 0)
+        let folder = xbel.new_folder("empty");
+        if let Resolved::Folder(items) = xbel.resolve_folder_mut(&XbelPath::Id(2)) {
+            items.push(folder);
         }
+        assert_eq!(xbel.max_depth(), 3);
+        assert_eq!(xbel.get_folder(6).unwrap().max_depth(), 0);
+        Ok(())
     }
 
-    impl Clone for Folder {
-        fn clone(&self) -> Self {
-            Self {
-                id: self.id.clone(),
-                title: self.title.clone(),
-                items: self.items.clone(),
-            }
-        }
+    #[test]
+    fn depth_iterator_unbalanced_end() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        let mut nesting_iter = XbelNestingIterator::new(&xbel);
+        // Malformed: an End without a matching folder (before any item)
+        nesting_iter.to_process.push_back(XbelItemOrEnd::End {
+            id: "42".to_string(),
+            title: "unbalanced".to_string(),
+        });
+        nesting_iter.initial = false;
+        nesting_iter
+            .to_process
+            .extend(xbel.items.iter().map(XbelItemOrEnd::Item));
+        let depth_iter = XbelDepthIterator {
+            nesting_iter,
+            depth: 0,
+        };
+        let depths = depth_iter.map(|(depth, _)| depth).collect::<Vec<_>>();
+        assert_eq!(depths, vec![0, 1, 2, 2, 1]);
+        Ok(())
     }
 
     #[test]
@@ -603,7 +2006,7 @@ mod tests {
         assert_eq!(i.get_id(), "4");
         let i = xbel_it.next().unwrap();
         assert_eq!(i.get_id(), "5");
-        assert_eq!(xbel_it.next().is_none(), true);
+        assert!(xbel_it.next().is_none());
 
         let xbel_it2 = XbelIterator::new(&xbel);
         let bookmarks_only = xbel_it2
@@ -617,6 +2020,11 @@ mod tests {
             .collect::<Vec<&Bookmark>>();
 
         assert_eq!(bookmarks_only.len(), 3);
+        assert_eq!(xbel.bookmarks().collect::<Vec<&Bookmark>>(), bookmarks_only);
+        assert_eq!(
+            xbel.folders().map(|f| f.id.as_str()).collect::<Vec<&str>>(),
+            vec!["1", "2"]
+        );
 
         Ok(())
     }
@@ -647,16 +2055,18 @@ mod tests {
         // bookmark id 4
         let _i = xbel_it.next().unwrap();
         let i = xbel_it.next().unwrap();
-        if let XbelItemOrEnd::End(folder_id) = &i {
-            assert_eq!(folder_id, "2");
+        if let XbelItemOrEnd::End { id, title } = &i {
+            assert_eq!(id, "2");
+            assert_eq!(title, "bank");
         } else {
             panic!("Expecting a end folder marker");
         }
         // bookmark id 5
         let _i = xbel_it.next().unwrap();
         let i = xbel_it.next().unwrap();
-        if let XbelItemOrEnd::End(folder_id) = &i {
-            assert_eq!(folder_id, "1");
+        if let XbelItemOrEnd::End { id, title } = &i {
+            assert_eq!(id, "1");
+            assert_eq!(title, "admin");
         } else {
             panic!("Expecting a end folder marker");
         }
@@ -666,20 +2076,460 @@ mod tests {
 
     #[test]
     #[traced_test]
-    fn xbel_highest_id() -> Result<(), quick_xml::errors::serialize::DeError> {
-        // Try to read a valid xbel file and to iterate over content
+    fn xbel_nesting_iter_from_item() -> Result<(), quick_xml::errors::serialize::DeError> {
         let xbel: Xbel = from_str(XBEL_BANK)?;
-        assert_eq!(xbel.get_highest_id(), 5);
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(2)).unwrap();
+        let ids = XbelNestingIterator::from_item(&items[item_index])
+            .map(|i| match i {
+                XbelItemOrEnd::Item(item) => item.get_id().clone(),
+                XbelItemOrEnd::End { id, .. } => format!("/{}", id),
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(ids, vec!["2", "3", "4", "/2"]);
         Ok(())
     }
 
     #[test]
-    #[traced_test]
-    fn add_xbel_empty() -> Result<(), quick_xml::errors::serialize::DeError> {
-        // Add bookmark to empty Xbel
-        let mut xbel: Xbel = from_str(XBEL_EMPTY)?;
-        println!("xbel: {:?}", xbel);
-        assert_eq!(xbel.get_highest_id(), 0);
+    fn xbel_iter_with_depth() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        let depths = xbel
+            .iter_with_depth()
+            .map(|(depth, item)| (depth, item.get_id().as_str()))
+            .collect::<Vec<(usize, &str)>>();
+        assert_eq!(
+            depths,
+            vec![(0, "1"), (1, "2"), (2, "3"), (2, "4"), (1, "5")]
+        );
+
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(2)).unwrap();
+        let depths = XbelDepthIterator::from_item(&items[item_index])
+            .map(|(depth, item)| (depth, item.get_id().as_str()))
+            .collect::<Vec<(usize, &str)>>();
+        assert_eq!(depths, vec![(0, "2"), (1, "3"), (1, "4")]);
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn xbel_highest_id() -> Result<(), quick_xml::errors::serialize::DeError> {
+        // Try to read a valid xbel file and to iterate over content
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        assert_eq!(xbel.get_highest_id(), 5);
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn xbel_stored_highest_id() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+        let xbel = Xbel::from_xml_str(XBEL_EMPTY)?;
+        assert_eq!(xbel.stored_highest_id(), Some(0));
+        let xbel = Xbel::from_xml_str(&XBEL_BANK.replace(":5:", ":12:"))?;
+        assert_eq!(xbel.stored_highest_id(), Some(12));
+        assert_eq!(xbel.get_highest_id(), 5);
+        let xbel = Xbel::from_xml_str(&XBEL_BANK.replace("highestId :5:", "foo"))?;
+        assert_eq!(xbel.stored_highest_id(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_item_cmp() {
+        let mut items = vec![
+            XbelItem::new_bookmark("1", "https://b.com", "bank"),
+            XbelItem::new_folder("2", "work", None),
+            XbelItem::new_bookmark("3", "https://A.com", "Zoo"),
+            XbelItem::new_folder("4", "Admin", None),
+            XbelItem::new_bookmark("5", "https://c.com", "Bank"),
+        ];
+        let ids = |items: &[XbelItem]| {
+            items
+                .iter()
+                .map(|i| i.get_id().as_str())
+                .collect::<String>()
+        };
+
+        items.sort_by(|a, b| a.cmp_by_title(b, false));
+        assert_eq!(ids(&items), "42531");
+        items.sort_by(|a, b| a.cmp_by_title(b, true));
+        assert_eq!(ids(&items), "42513");
+        items.sort_by(|a, b| a.cmp_by_url(b, false));
+        assert_eq!(ids(&items), "42315");
+        items.sort_by(|a, b| a.cmp_by_url(b, true));
+        assert_eq!(ids(&items), "42315");
+
+        // Same url: compared by title
+        let a = XbelItem::new_bookmark("1", "https://a.com", "b");
+        let b = XbelItem::new_bookmark("2", "https://a.com", "a");
+        assert_eq!(a.cmp_by_url(&b, true), Ordering::Greater);
+    }
+
+    #[test]
+    fn read_xbel_from_str() -> Result<(), XbelError> {
+        let xbel = XBEL_BANK.parse::<Xbel>()?;
+        assert_eq!(xbel, Xbel::try_from_reader(XBEL_BANK.as_bytes())?);
+        assert_eq!(xbel.count(), (2, 3));
+        // Unlike quick_xml::de::from_str, the highestId comment is read
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+        assert_eq!(from_str::<Xbel>(XBEL_BANK)?.stored_highest_id(), None);
+
+        let content = XBEL_BANK.replace("<title>admin</title>", "\n=======\n");
+        assert!(matches!(
+            Xbel::from_str(&content),
+            Err(XbelError::MergeConflict(_))
+        ));
+        assert!(Xbel::from_str("<xbel><folder></xbel>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_merge_conflict() {
+        let content = XBEL_BANK.trim_start().replace(
+            "<title>admin</title>",
+            "\n<<<<<<< HEAD\n<title>admin</title>\n=======\n<title>Admin</title>\n>>>>>>> 1a2b3c4",
+        );
+        let res = Xbel::try_from_reader(content.as_bytes());
+        assert!(matches!(res, Err(XbelError::MergeConflict(8))));
+
+        // Compressed content is checked too
+        let mut compressed = GzEncoder::new(Vec::new(), Compression::default());
+        compressed.write_all(content.as_bytes()).unwrap();
+        let res = Xbel::try_from_reader(compressed.finish().unwrap().as_slice());
+        assert!(matches!(res, Err(XbelError::MergeConflict(8))));
+
+        // Not a conflict marker (not at the start of a line)
+        assert_eq!(find_conflict_marker(b"<title>a =======</title>"), None);
+        assert_eq!(find_conflict_marker(b"a\r\n=======\r\nb"), Some(2));
+    }
+
+    #[test]
+    fn xbel_url_round_trip() -> Result<(), XbelError> {
+        let hrefs = [
+            "file:///home/me/My%20Docs/x%2Fy.pdf",
+            "file:///C:/Users/me/My%20Docs/a%26b.pdf",
+            "ftp://ftp.example.com/pub/a%20b.txt",
+            "obsidian://open?vault=notes&file=a%20b",
+            "https://example.com/search?q=a%2Bb&lang=en#top",
+        ];
+        let items = hrefs
+            .iter()
+            .enumerate()
+            .map(|(i, href)| XbelItem::new_bookmark(&(i + 1).to_string(), href, "Doc"))
+            .collect();
+        let xbel = Xbel::new(Some(items));
+
+        // href are preserved byte for byte
+        let read = Xbel::try_from_reader(xbel.try_to_string()?.as_bytes())?;
+        let read_hrefs = read
+            .into_iter()
+            .filter_map(|item| item.get_url().cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(read_hrefs, hrefs);
+        Ok(())
+    }
+
+    #[test]
+    fn folder_is_empty() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let Some(XbelItem::Folder(admin)) = xbel.items.first() else {
+            panic!("Expected folder admin");
+        };
+        assert!(!admin.is_empty());
+        assert_eq!(admin.child_count(), 2);
+        assert_eq!(admin.count_descendants(), (1, 3));
+        let folder = Folder::new("10", "empty", None);
+        assert!(folder.is_empty());
+        assert_eq!(folder.child_count(), 0);
+        assert_eq!(folder.count_descendants(), (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_prune_empty_folders() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert!(xbel.prune_empty_folders().is_empty());
+        assert_eq!(xbel.count(), (2, 3));
+
+        // Folder 2 empty, folder 6 (empty) containing folder 7 (empty) in folder 1
+        xbel.remove_by_id(3);
+        xbel.remove_by_id(4);
+        let folder_6 =
+            XbelItem::new_folder("6", "a", Some(vec![XbelItem::new_folder("7", "b", None)]));
+        let Some(XbelItem::Folder(admin)) = xbel.items.first_mut() else {
+            panic!("Expected folder admin");
+        };
+        admin.items.push(folder_6);
+        assert_eq!(xbel.prune_empty_folders(), vec!["2", "7", "6"]);
+        assert_eq!(xbel.count(), (1, 1));
+        assert!(xbel.get_folder(1).is_some());
+
+        // Folder 1 empty (removed as well)
+        xbel.remove_by_id(5);
+        assert_eq!(xbel.prune_empty_folders(), vec!["1"]);
+        assert!(xbel.items.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_next_id() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(&XBEL_BANK.replace(":5:", ":8:"))?;
+        // Ids are allocated even if not used yet
+        assert_eq!(xbel.next_id(), 9);
+        let bookmark = xbel.new_bookmark("https://example.com", "Example");
+        assert_eq!(bookmark.get_id(), "10");
+        assert_eq!(xbel.new_folder("Example").get_id(), "11");
+        // An item added with a higher id is taken into account
+        xbel.items
+            .push(XbelItem::new_bookmark("20", "https://a.com", "A"));
+        assert_eq!(xbel.next_id(), 21);
+        // Allocated ids are written in the highestId comment
+        let xbel = Xbel::from_xml_str(&xbel.try_to_string()?)?;
+        assert_eq!(xbel.stored_highest_id(), Some(21));
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_new_bookmark_after_remove() -> Result<(), XbelError> {
+        // Remove the item with the highest id: its id must not be reused
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert!(xbel.remove_by_id(5).is_some());
+        assert_eq!(xbel.get_highest_id(), 4);
+        // highestId is preserved when writing (and read again)
+        let mut xbel = Xbel::from_xml_str(&xbel.try_to_string()?)?;
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+        let bookmark = xbel.new_bookmark("https://example.com", "Example");
+        assert_eq!(bookmark.get_id(), "6");
+
+        // A stale (lower) stored highestId is ignored
+        let mut xbel = Xbel::from_xml_str(&XBEL_BANK.replace(":5:", ":2:"))?;
+        assert_eq!(
+            xbel.new_bookmark("https://example.com", "Example").get_id(),
+            "6"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn read_xbel_from_reader() -> Result<(), XbelError> {
+        let xbel = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        assert_eq!(xbel.items.len(), 1);
+        assert_eq!(xbel.get_highest_id(), 5);
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+
+        let bank_v1 = std::fs::read("../ressources/bookmarks_bank_v1.xbel")?;
+        let xbel = Xbel::try_from_reader(std::io::Cursor::new(bank_v1))?;
+        assert_eq!(xbel.get_highest_id(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_missing_ids() -> Result<(), XbelError> {
+        // Folder 1 & bookmark 4 have no id attribute
+        let xbel = Xbel::try_from_file("../ressources/bookmarks_no_id.xbel")?;
+        assert_eq!(xbel.count(), (2, 2));
+        assert_eq!(xbel.get_highest_id(), 3);
+        assert_eq!(xbel.stored_highest_id(), None);
+        assert!(xbel.items[0].has_missing_id());
+        assert!(xbel.get_bookmark(3).is_some());
+
+        // Missing ids are assigned when writing (DFS order)
+        let written = Xbel::try_from_reader(xbel.to_string().as_bytes())?;
+        assert_eq!(written.get_folder(4).unwrap().title.text, "admin");
+        assert_eq!(
+            written.get_bookmark(5).unwrap().href,
+            "https://www.bank2.com/"
+        );
+        assert_eq!(written.stored_highest_id(), Some(5));
+        assert!(written.validate().is_empty());
+
+        let mut xbel = xbel;
+        assert_eq!(xbel.assign_missing_ids(), 2);
+        assert_eq!(xbel.items, written.items);
+        assert_eq!(xbel.assign_missing_ids(), 0);
+        assert_eq!(xbel.next_id(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn ids_xbel() -> Result<(), XbelError> {
+        let xbel = Xbel::from_str(XBEL_BANK)?;
+        assert_eq!(xbel.ids().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+
+        // Missing, non numeric ids and aliases are skipped, duplicates are kept
+        let mut xbel = Xbel::from_str(&XBEL_BANK.replace("id=\"4\"", "id=\"b4\""))?;
+        xbel.items
+            .push(XbelItem::new_bookmark("", "https://a.b/", "A"));
+        xbel.items
+            .push(XbelItem::new_bookmark("3", "https://c.d/", "C"));
+        xbel.items.push(XbelItem::Alias {
+            reference: "1".to_string(),
+        });
+        assert_eq!(xbel.ids().collect::<Vec<_>>(), vec![1, 2, 3, 5, 3]);
+        assert_eq!(xbel.get_highest_id(), 5);
+        assert_eq!(xbel.items[1].numeric_id(), None);
+        assert_eq!(Xbel::new(None).ids().next(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn write_xbel_to_writer() -> Result<(), XbelError> {
+        let xbel = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let mut buffer = Vec::new();
+        xbel.to_writer(&mut buffer)?;
+        assert_eq!(buffer, xbel.try_to_string()?.into_bytes());
+        assert_eq!(xbel.try_to_string()?, xbel.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn write_xbel_empty_folder_and_title() -> Result<(), XbelError> {
+        let xbel = Xbel::new(Some(vec![
+            XbelItem::new_folder("1", "Empty", None),
+            XbelItem::new_folder("2", "", None),
+            XbelItem::new_bookmark("3", "https://www.example.com/", ""),
+        ]));
+        let content = xbel.try_to_string()?;
+        assert!(content.contains("<folder id=\"1\">\n  <title>Empty</title>\n</folder>"));
+        assert!(content.contains("<folder id=\"2\">\n  <title></title>\n</folder>"));
+        assert!(content.contains("<title></title>\n</bookmark>"));
+        assert!(!content.contains("/>"));
+        // Re-parsing our own output always succeeds (and is stable)
+        let xbel_ = Xbel::try_from_reader(content.as_bytes())?;
+        assert_eq!(xbel_.items, xbel.items);
+        assert_eq!(xbel_.try_to_string()?, content);
+
+        // Self closing variants (as written by other tools) are read the same way
+        let other = r#"<xbel version="1.0"><folder id="1"><title>Empty</title></folder>
+<folder id="2"><title/></folder><bookmark href="https://www.example.com/" id="3"/></xbel>"#;
+        assert_eq!(Xbel::try_from_reader(other.as_bytes())?.items, xbel.items);
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_uppercase_namespaced_root() -> Result<(), XbelError> {
+        let content = XBEL_BANK
+            .replace(
+                "<xbel version=\"1.0\">",
+                "<XBEL xmlns=\"http://www.python.org/topics/xml/xbel/\" version=\"1.0\">",
+            )
+            .replace("</xbel>", "</XBEL>");
+        assert!(content.contains("<XBEL xmlns="));
+        let xbel = Xbel::try_from_reader(content.as_bytes())?;
+        let expected = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        assert_eq!(xbel, expected);
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+        // Always written with a lowercase root element
+        assert!(xbel.try_to_string()?.contains("<xbel version=\"1.0\">"));
+
+        // Namespace prefix
+        let content = r#"<x:xbel xmlns:x="http://www.python.org/topics/xml/xbel/" version="1.0">
+<x:folder id="1"><x:title>admin</x:title>
+<x:bookmark href="https://www.example.com/" id="2"><x:title>Example</x:title></x:bookmark>
+</x:folder></x:xbel>"#;
+        let xbel = Xbel::try_from_reader(content.as_bytes())?;
+        assert_eq!(xbel.count(), (1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn write_xbel_with_options() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert_eq!(
+            xbel.to_string_with_options(&WriteOptions::default())?,
+            xbel.to_string()
+        );
+
+        let options = WriteOptions {
+            highest_id_comment: false,
+            indent: 4,
+            ..Default::default()
+        };
+        let content = xbel.to_string_with_options(&options)?;
+        assert!(!content.contains("highestId"));
+        assert!(
+            content.contains("<xbel version=\"1.0\">\n<folder id=\"1\">\n    <title>admin</title>")
+        );
+        let xbel_ = Xbel::try_from_reader(content.as_bytes())?;
+        assert_eq!(xbel_.items, xbel.items);
+        assert_eq!(xbel_.stored_highest_id(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn read_write_xbel_alias() -> Result<(), XbelError> {
+        let content = XBEL_BANK.replace(
+            "</folder>\n            </xbel>",
+            "<alias ref=\"2\"/></folder><alias ref=\"42\"></alias></xbel>",
+        );
+        let xbel = Xbel::try_from_reader(content.as_bytes())?;
+        let aliases: Vec<&XbelItem> = xbel
+            .into_iter()
+            .filter(|item| matches!(item, XbelItem::Alias { .. }))
+            .collect();
+        assert_eq!(aliases.len(), 2, "{:?}", xbel.items);
+        assert_eq!(
+            aliases[0],
+            &XbelItem::Alias {
+                reference: "2".to_string()
+            }
+        );
+        assert_eq!(
+            xbel.resolve_alias(aliases[0])
+                .map(|item| item.get_title().text.as_str()),
+            Some("bank")
+        );
+        assert_eq!(xbel.resolve_alias(aliases[1]), None);
+        assert_eq!(xbel.resolve_alias(&xbel.items[0]), None);
+        // Aliases are leaves without id, title or url
+        assert_eq!(xbel.count(), Xbel::from_xml_str(XBEL_BANK)?.count());
+        assert_eq!(xbel.get_highest_id(), 5);
+        assert!(!aliases[0].has_missing_id());
+        assert_eq!(aliases[0].get_url(), None);
+
+        let written = xbel.to_string();
+        assert!(written.contains("  <alias ref=\"2\"/>\n</folder>\n<alias ref=\"42\"/>\n</xbel>"));
+        let xbel_ = Xbel::try_from_reader(written.as_bytes())?;
+        assert_eq!(xbel_.items, xbel.items);
+        Ok(())
+    }
+
+    #[test]
+    fn write_xbel_without_doctype() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert!(xbel.to_string().contains("<!DOCTYPE xbel"));
+
+        let options = WriteOptions {
+            doctype: false,
+            ..Default::default()
+        };
+        let content = xbel.to_string_with_options(&options)?;
+        assert!(!content.contains("DOCTYPE"));
+        assert!(content.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xbel version=\"1.0\">\n<!--- highestId :5:"
+        ));
+        let xbel_ = Xbel::try_from_reader(content.as_bytes())?;
+        assert_eq!(xbel_.items, xbel.items);
+        assert_eq!(xbel_.stored_highest_id(), Some(5));
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_with_bom() -> Result<(), XbelError> {
+        let content = format!("\u{FEFF}\n  {}", XBEL_BANK.trim_start());
+        let xbel = Xbel::try_from_reader(content.as_bytes())?;
+        assert_eq!(xbel.get_highest_id(), 5);
+        assert_eq!(xbel.stored_highest_id(), Some(5));
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn add_xbel_empty() -> Result<(), quick_xml::errors::serialize::DeError> {
+        // Add bookmark to empty Xbel
+        let mut xbel: Xbel = from_str(XBEL_EMPTY)?;
+        println!("xbel: {:?}", xbel);
+        assert_eq!(xbel.get_highest_id(), 0);
         let bookmark_id = (xbel.get_highest_id() + 1).to_string();
         let items_0 = xbel.get_items_mut(&XbelPath::Id(1));
         assert!(items_0.is_none());
@@ -710,6 +2560,948 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn xbel_path_split() {
+        assert_eq!(XbelPath::split_path("admin/bank"), vec!["admin", "bank"]);
+        assert_eq!(XbelPath::split_path(r"news/A\/B"), vec!["news", "A/B"]);
+        assert_eq!(XbelPath::split_path(r"C:\\/D"), vec![r"C:\", "D"]);
+        assert_eq!(XbelPath::split_path(r"a\b"), vec![r"a\b"]);
+        assert_eq!(XbelPath::escape_title(r"A/B\C"), r"A\/B\\C");
+        assert_eq!(
+            XbelPath::split_path(XbelPath::escape_title(r"A/B\C").as_str()),
+            vec![r"A/B\C"]
+        );
+    }
+
+    #[test]
+    fn get_items_xbel_escaped_path() -> Result<(), quick_xml::errors::serialize::DeError> {
+        const XBEL_SLASH: &str = r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+            <xbel version="1.0">
+            <!--- highestId :3: for Floccus bookmark sync browser extension -->
+            <folder id="1">
+                <title>news</title>
+                <folder id="2">
+                    <title>A/B</title>
+                    <bookmark href="https://www.example.com/" id="3">
+                        <title>Example</title>
+                    </bookmark>
+                </folder>
+            </folder>
+            </xbel>
+        "#;
+
+        let mut xbel: Xbel = from_str(XBEL_SLASH)?;
+        let xbel_path = XbelPath::Path(r"news/A\/B".to_string());
+        let (item_index, items) = xbel.get_items(&xbel_path).unwrap();
+        assert_eq!(items[item_index].get_id(), "2");
+        let (item_index, items) = xbel.get_items_mut(&xbel_path).unwrap();
+        assert_eq!(items[item_index].get_id(), "2");
+        assert!(xbel
+            .get_items(&XbelPath::Path("news/A/B".to_string()))
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn get_items_xbel_path_strict() -> Result<(), quick_xml::errors::serialize::DeError> {
+        const XBEL_DUP: &str = r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+            <xbel version="1.0">
+            <!--- highestId :6: for Floccus bookmark sync browser extension -->
+            <folder id="1">
+                <title>a</title>
+                <folder id="2">
+                    <title>x</title>
+                    <folder id="6">
+                        <title>b</title>
+                    </folder>
+                </folder>
+            </folder>
+            <folder id="3">
+                <title>c</title>
+                <folder id="4">
+                    <title>b</title>
+                    <folder id="5">
+                        <title>a</title>
+                    </folder>
+                </folder>
+            </folder>
+            </xbel>
+        "#;
+
+        let mut xbel: Xbel = from_str(XBEL_DUP)?;
+        // "b" is only in folders "c" and "a/x" (not directly in "a"): no match
+        assert!(xbel.get_items(&XbelPath::Path("a/b".to_string())).is_none());
+        assert!(xbel
+            .get_items_mut(&XbelPath::Path("a/b".to_string()))
+            .is_none());
+        assert!(matches!(
+            xbel.resolve_folder_mut(&XbelPath::Path("a/b".to_string())),
+            Resolved::NotFound
+        ));
+        let (item_index, items) = xbel
+            .get_items(&XbelPath::Path("a/x/b".to_string()))
+            .unwrap();
+        assert_eq!(items[item_index].get_id(), "6");
+        let (item_index, items) = xbel.get_items(&XbelPath::Path("c/b".to_string())).unwrap();
+        assert_eq!(items[item_index].get_id(), "4");
+        // path starts at root level
+        assert!(xbel.get_items(&XbelPath::Path("b".to_string())).is_none());
+        let (item_index, items) = xbel.get_items(&XbelPath::Path("a".to_string())).unwrap();
+        assert_eq!(items[item_index].get_id(), "1");
+        let (item_index, items) = xbel
+            .get_items_mut(&XbelPath::Path("c/b/a".to_string()))
+            .unwrap();
+        assert_eq!(items[item_index].get_id(), "5");
+        Ok(())
+    }
+
+    #[test]
+    fn build_xbel() {
+        let mut xbel = Xbel::new(Some(vec![XbelItem::new_folder(
+            "1",
+            "admin",
+            Some(vec![XbelItem::new_bookmark(
+                "2",
+                "https://www.bank1.com/",
+                "Bank 1",
+            )]),
+        )]));
+        let folder = xbel.new_folder("insurance");
+        assert_eq!(folder.get_id(), "3");
+        let (item_index, items) = xbel.get_items_mut(&XbelPath::Id(1)).unwrap();
+        if let XbelItem::Folder(f) = &mut items[item_index] {
+            f.items.push(folder);
+        }
+        assert_eq!(xbel.get_highest_id(), 3);
+        let xbel_ = Xbel::try_from_reader(xbel.to_string().as_bytes()).unwrap();
+        assert_eq!(xbel_.items, xbel.items);
+    }
+
+    #[test]
+    fn remove_by_id_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let mut xbel: Xbel = from_str(XBEL_BANK)?;
+        // nested bookmark
+        let removed = xbel.remove_by_id(4).unwrap();
+        assert_eq!(removed.get_id(), "4");
+        assert!(xbel.get_items(&XbelPath::Id(4)).is_none());
+        assert!(xbel.remove_by_id(4).is_none());
+        // folder (with children)
+        let removed = xbel.remove_by_id(2).unwrap();
+        if let XbelItem::Folder(f) = removed {
+            assert_eq!(f.items.len(), 1);
+        } else {
+            panic!("Expecting a folder");
+        }
+        assert!(xbel.get_items(&XbelPath::Id(3)).is_none());
+        assert_eq!(xbel.get_highest_id(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn replace_subtree_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let mut xbel: Xbel = from_str(XBEL_BANK)?;
+        // nested folder (with children), position is kept
+        let folder = XbelItem::new_folder(
+            "6",
+            "bank (new)",
+            Some(vec![XbelItem::new_bookmark(
+                "7",
+                "https://www.bank4.com",
+                "Bank 4",
+            )]),
+        );
+        let old = xbel.replace_subtree(2, folder).unwrap();
+        assert_eq!(old.get_title().text, "bank");
+        assert!(xbel.get_items(&XbelPath::Id(3)).is_none());
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(6)).unwrap();
+        assert_eq!(item_index, 0);
+        assert_eq!(items.len(), 2);
+        assert_eq!(xbel.find_path_of(7).unwrap().len(), 2);
+        // leaf bookmark
+        let bookmark = XbelItem::new_bookmark("5", "https://www.bank5.com", "Bank 5");
+        let old = xbel.replace_subtree(5, bookmark.clone()).unwrap();
+        assert_eq!(old.get_url().unwrap(), "https://www.bank3.com");
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(5)).unwrap();
+        assert_eq!(item_index, 1);
+        assert_eq!(items[item_index], bookmark);
+        // not found
+        assert!(xbel.replace_subtree(42, bookmark).is_none());
+        assert_eq!(xbel.get_highest_id(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_folder_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let mut xbel: Xbel = from_str(XBEL_BANK)?;
+        assert!(matches!(
+            xbel.resolve_folder_mut(&XbelPath::Root),
+            Resolved::Folder(items) if items.len() == 1
+        ));
+        assert!(matches!(
+            xbel.resolve_folder_mut(&XbelPath::Path("admin/bank".to_string())),
+            Resolved::Folder(items) if items.len() == 2
+        ));
+        // Found but not a folder
+        match xbel.resolve_folder_mut(&XbelPath::Path(
+            "admin/bank/Bank 2 because 2 > 1 !#€".to_string(),
+        )) {
+            Resolved::Leaf(b) => assert_eq!(b.id, "4"),
+            r => panic!("Expecting a leaf, got: {:?}", r),
+        }
+        assert!(matches!(
+            xbel.resolve_folder_mut(&XbelPath::Id(5)),
+            Resolved::Leaf(_)
+        ));
+        assert_eq!(
+            xbel.resolve_folder_mut(&XbelPath::Path("admin/news".to_string())),
+            Resolved::NotFound
+        );
+        assert_eq!(
+            xbel.resolve_folder_mut(&XbelPath::Id(42)),
+            Resolved::NotFound
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn move_item_xbel() -> Result<(), XbelMoveError> {
+        let ids = |xbel: &Xbel| {
+            XbelNestingIterator::new(xbel)
+                .map(|i| match i {
+                    XbelItemOrEnd::Item(item) => item.get_id().clone(),
+                    XbelItemOrEnd::End { id, .. } => format!("/{}", id),
+                })
+                .collect::<Vec<String>>()
+        };
+
+        let mut xbel: Xbel = from_str(XBEL_BANK).unwrap();
+        // bookmark 5 as first item of folder 2
+        xbel.move_item(5, &XbelPath::Id(2), Placement::InFolderPrepend)?;
+        assert_eq!(ids(&xbel), vec!["1", "2", "5", "3", "4", "/2", "/1"]);
+        // bookmark 3 before bookmark 5
+        xbel.move_item(3, &XbelPath::Id(5), Placement::Before)?;
+        assert_eq!(ids(&xbel), vec!["1", "2", "3", "5", "4", "/2", "/1"]);
+        // folder 2 at root level (after folder 1)
+        xbel.move_item(2, &XbelPath::Id(1), Placement::After)?;
+        assert_eq!(ids(&xbel), vec!["1", "/1", "2", "3", "5", "4", "/2"]);
+        // bookmark 4 in folder "admin"
+        xbel.move_item(
+            4,
+            &XbelPath::Path("admin".to_string()),
+            Placement::InFolderAppend,
+        )?;
+        assert_eq!(ids(&xbel), vec!["1", "4", "/1", "2", "3", "5", "/2"]);
+        // folder 1 at root level (last)
+        xbel.move_item(1, &XbelPath::Root, Placement::InFolderAppend)?;
+        assert_eq!(ids(&xbel), vec!["2", "3", "5", "/2", "1", "4", "/1"]);
+
+        // Errors
+        let mut xbel: Xbel = from_str(XBEL_BANK).unwrap();
+        assert!(matches!(
+            xbel.move_item(1, &XbelPath::Id(2), Placement::InFolderAppend),
+            Err(XbelMoveError::Cycle(1, _))
+        ));
+        assert!(matches!(
+            xbel.move_item(2, &XbelPath::Id(2), Placement::After),
+            Err(XbelMoveError::Cycle(2, _))
+        ));
+        assert!(matches!(
+            xbel.move_item(42, &XbelPath::Id(2), Placement::After),
+            Err(XbelMoveError::NotFound(42))
+        ));
+        assert!(matches!(
+            xbel.move_item(3, &XbelPath::Id(42), Placement::After),
+            Err(XbelMoveError::TargetNotFound(_))
+        ));
+        assert!(matches!(
+            xbel.move_item(3, &XbelPath::Id(5), Placement::InFolderAppend),
+            Err(XbelMoveError::TargetNotAFolder(_))
+        ));
+        assert!(matches!(
+            xbel.move_item(3, &XbelPath::Root, Placement::Before),
+            Err(XbelMoveError::InvalidPlacement(_))
+        ));
+        // Nothing changed
+        assert_eq!(xbel, from_str(XBEL_BANK).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn insert_at_path_xbel() -> Result<(), XbelInsertError> {
+        let ids = |items: &[XbelItem]| {
+            items
+                .iter()
+                .map(|i| i.get_id().clone())
+                .collect::<Vec<String>>()
+        };
+        let bookmark = |id: &str| XbelItem::new_bookmark(id, "https://example.com", "Example");
+
+        let mut xbel = Xbel::from_str(XBEL_BANK).unwrap();
+        // Root level
+        xbel.insert_at_path(&XbelPath::Root, bookmark("6"), Placement::InFolderPrepend)?;
+        xbel.insert_at_path(&XbelPath::Root, bookmark("7"), Placement::InFolderAppend)?;
+        assert_eq!(ids(&xbel.items), vec!["6", "1", "7"]);
+        // Before / after an item (folder or bookmark), by id or by path
+        xbel.insert_at_path(&XbelPath::Id(1), bookmark("8"), Placement::After)?;
+        xbel.insert_at_path(&XbelPath::Id(6), bookmark("9"), Placement::Before)?;
+        assert_eq!(ids(&xbel.items), vec!["9", "6", "1", "8", "7"]);
+        xbel.insert_all_at_path(
+            &XbelPath::Path("admin/bank".to_string()),
+            vec![bookmark("10"), bookmark("11")],
+            Placement::After,
+        )?;
+        assert_eq!(
+            ids(&xbel.get_folder(1).unwrap().items),
+            vec!["2", "10", "11", "5"]
+        );
+        // In a folder
+        xbel.insert_all_at_path(
+            &XbelPath::Id(2),
+            vec![bookmark("12"), bookmark("13")],
+            Placement::InFolderPrepend,
+        )?;
+        xbel.insert_at_path(
+            &XbelPath::Path("admin/bank".to_string()),
+            bookmark("14"),
+            Placement::InFolderAppend,
+        )?;
+        assert_eq!(
+            ids(&xbel.get_folder(2).unwrap().items),
+            vec!["12", "13", "3", "4", "14"]
+        );
+
+        // Errors: nothing inserted
+        let xbel_before = xbel.clone();
+        assert!(matches!(
+            xbel.insert_at_path(&XbelPath::Id(42), bookmark("15"), Placement::After),
+            Err(XbelInsertError::NotFound(XbelPath::Id(42)))
+        ));
+        assert!(matches!(
+            xbel.insert_at_path(
+                &XbelPath::Path("admin/news".to_string()),
+                bookmark("15"),
+                Placement::InFolderAppend
+            ),
+            Err(XbelInsertError::NotFound(_))
+        ));
+        assert!(matches!(
+            xbel.insert_at_path(&XbelPath::Id(5), bookmark("15"), Placement::InFolderAppend),
+            Err(XbelInsertError::NotaFolder(id)) if id == "5"
+        ));
+        assert!(matches!(
+            xbel.insert_at_path(&XbelPath::Root, bookmark("15"), Placement::Before),
+            Err(XbelInsertError::InvalidPlacement(XbelPath::Root))
+        ));
+        assert_eq!(xbel, xbel_before);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_item_xbel() -> Result<(), XbelMoveError> {
+        let ids = |xbel: &Xbel| {
+            XbelNestingIterator::new(xbel)
+                .map(|i| match i {
+                    XbelItemOrEnd::Item(item) => item.get_id().clone(),
+                    XbelItemOrEnd::End { id, .. } => format!("/{}", id),
+                })
+                .collect::<Vec<String>>()
+        };
+
+        let mut xbel = Xbel::from_str(XBEL_BANK).unwrap();
+        // bookmark 5 after bookmark 3
+        let mapping = xbel.copy_item(5, &XbelPath::Id(3), Placement::After)?;
+        assert_eq!(mapping, HashMap::from([("5".to_string(), "6".to_string())]));
+        assert_eq!(ids(&xbel), vec!["1", "2", "3", "6", "4", "/2", "5", "/1"]);
+        assert_eq!(xbel.get_bookmark(6).unwrap().href, "https://www.bank3.com");
+        // folder 2 (with its children) into itself: new ids for the folder and its children
+        let mapping = xbel.copy_item(2, &XbelPath::Id(2), Placement::InFolderAppend)?;
+        assert_eq!(mapping.len(), 4);
+        assert_eq!(mapping.get("2"), Some(&"7".to_string()));
+        assert_eq!(
+            ids(&xbel),
+            vec!["1", "2", "3", "6", "4", "7", "8", "9", "10", "/7", "/2", "5", "/1"]
+        );
+        assert_eq!(xbel.get_folder(7).unwrap().title.text, "bank");
+        // Note: stored highestId is only updated when writing
+        assert!(!xbel
+            .validate()
+            .iter()
+            .any(|issue| matches!(issue, crate::ValidationIssue::DuplicateId(_))));
+        // at root level
+        xbel.copy_item(4, &XbelPath::Root, Placement::InFolderPrepend)?;
+        assert_eq!(xbel.items[0].get_id(), "11");
+
+        // Errors (same as move_item), nothing changed
+        let mut xbel = Xbel::from_str(XBEL_BANK).unwrap();
+        assert!(matches!(
+            xbel.copy_item(42, &XbelPath::Id(2), Placement::After),
+            Err(XbelMoveError::NotFound(42))
+        ));
+        assert!(matches!(
+            xbel.copy_item(3, &XbelPath::Id(5), Placement::InFolderAppend),
+            Err(XbelMoveError::TargetNotAFolder(_))
+        ));
+        assert!(matches!(
+            xbel.copy_item(3, &XbelPath::Root, Placement::Before),
+            Err(XbelMoveError::InvalidPlacement(_))
+        ));
+        assert_eq!(xbel, Xbel::from_str(XBEL_BANK).unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn move_folder_relatively_to_sibling() -> Result<(), XbelMoveError> {
+        let ids = |xbel: &Xbel| {
+            XbelNestingIterator::new(xbel)
+                .map(|i| match i {
+                    XbelItemOrEnd::Item(item) => item.get_id().clone(),
+                    XbelItemOrEnd::End { id, .. } => format!("/{}", id),
+                })
+                .collect::<Vec<String>>()
+        };
+
+        // Folder 6 (with bookmark 7) at root level (after folder 1)
+        let mut xbel: Xbel = from_str(XBEL_BANK).unwrap();
+        xbel.items.push(XbelItem::new_folder(
+            "6",
+            "news",
+            Some(vec![XbelItem::new_bookmark(
+                "7",
+                "https://news.com",
+                "News",
+            )]),
+        ));
+        // folder 1 after its sibling folder 6
+        xbel.move_item(1, &XbelPath::Id(6), Placement::After)?;
+        assert_eq!(
+            ids(&xbel),
+            vec!["6", "7", "/6", "1", "2", "3", "4", "/2", "5", "/1"]
+        );
+        // folder 1 before its sibling folder 6
+        xbel.move_item(1, &XbelPath::Id(6), Placement::Before)?;
+        assert_eq!(
+            ids(&xbel),
+            vec!["1", "2", "3", "4", "/2", "5", "/1", "6", "7", "/6"]
+        );
+        // folder 2 after bookmark 7 (folder 6)
+        xbel.move_item(2, &XbelPath::Id(7), Placement::After)?;
+        assert_eq!(
+            ids(&xbel),
+            vec!["1", "5", "/1", "6", "7", "2", "3", "4", "/2", "/6"]
+        );
+
+        // Folder 6 next to one of its descendants (bookmark 3 in folder 2)
+        assert!(matches!(
+            xbel.move_item(6, &XbelPath::Id(3), Placement::Before),
+            Err(XbelMoveError::Cycle(6, id)) if id == "3"
+        ));
+        assert!(matches!(
+            xbel.move_item(6, &XbelPath::Id(2), Placement::InFolderPrepend),
+            Err(XbelMoveError::Cycle(6, id)) if id == "2"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn new_bookmarks_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let mut xbel: Xbel = from_str(XBEL_BANK)?;
+        let highest_id = xbel.get_highest_id();
+        let bookmarks = xbel.new_bookmarks([
+            ("https://www.example_bank.com", "Example bank"),
+            ("https://www.example_bank2.com", "Example bank 2"),
+        ]);
+        let ids: Vec<&String> = bookmarks.iter().map(|b| b.get_id()).collect();
+        assert_eq!(
+            ids,
+            vec![&(highest_id + 1).to_string(), &(highest_id + 2).to_string()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_item_mut_accessors() {
+        let mut folder = XbelItem::new_folder("1", "Folder", None);
+        folder.get_title_mut().unwrap().text = "Renamed".to_string();
+        assert_eq!(folder.get_title().text, "Renamed");
+        assert_eq!(folder.get_url_mut(), None);
+
+        let mut bookmark = XbelItem::new_bookmark("2", "https://www.example.com/", "Example");
+        if let Some(url) = bookmark.get_url_mut() {
+            url.push_str("index.html");
+        }
+        bookmark.get_title_mut().unwrap().text.push_str(" site");
+        assert_eq!(
+            bookmark.get_url().map(String::as_str),
+            Some("https://www.example.com/index.html")
+        );
+        assert_eq!(bookmark.get_title().text, "Example site");
+
+        let mut alias = XbelItem::Alias {
+            reference: "2".to_string(),
+        };
+        assert_eq!(alias.get_title_mut(), None);
+        assert_eq!(alias.get_url_mut(), None);
+    }
+
+    #[test]
+    fn xbel_item_setters() {
+        let mut folder = XbelItem::new_folder("1", "Folder", None);
+        folder.set_title("Renamed");
+        assert_eq!(folder.get_title(), &Title::new("Renamed"));
+
+        let mut bookmark = XbelItem::new_bookmark("2", "https://www.example.com/", "Example");
+        bookmark.set_title("");
+        assert_eq!(bookmark.get_title().text, "");
+        if let XbelItem::Bookmark(b) = &mut bookmark {
+            b.set_href("https://www.rust-lang.org/");
+        }
+        assert_eq!(
+            bookmark.get_url().map(String::as_str),
+            Some("https://www.rust-lang.org/")
+        );
+    }
+
+    #[test]
+    fn create_folder_path_xbel() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let highest_id = xbel.get_highest_id();
+
+        // Already existing
+        assert!(xbel.create_folder_path("admin/bank").is_empty());
+        assert_eq!(xbel.get_highest_id(), highest_id);
+
+        let created = xbel.create_folder_path("admin/bank/2024/Q1");
+        assert_eq!(created, vec![highest_id + 1, highest_id + 2]);
+        let (item_index, items) = xbel
+            .get_items(&XbelPath::Path("admin/bank/2024".to_string()))
+            .unwrap();
+        assert_eq!(items[item_index].get_id(), &(highest_id + 1).to_string());
+        assert_eq!(items.len() - 1, item_index); // appended
+        let (item_index, items) = xbel
+            .get_items(&XbelPath::Path("admin/bank/2024/Q1".to_string()))
+            .unwrap();
+        assert_eq!(items[item_index].get_id(), &(highest_id + 2).to_string());
+
+        // From root level
+        assert_eq!(xbel.create_folder_path("news"), vec![highest_id + 3]);
+        assert_eq!(xbel.get_highest_id(), highest_id + 3);
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_to_markdown() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let expected = r#"- **admin**
+  - **bank**
+    - [Bank 1 - Best bank in the world](https://www.bank1.com/)
+    - [Bank 2 because 2 \> 1 !\#€](https://www.bank2.com)
+  - [My current bank U+1F929](https://www.bank3.com)
+"#;
+        assert_eq!(xbel.to_markdown(), expected);
+
+        let folder = xbel.new_folder("*notes* [draft]");
+        let bookmark = xbel.new_bookmark("https://www.example.com/a (b)", "");
+        let xbel = Xbel::new(Some(vec![folder, bookmark]));
+        assert_eq!(
+            xbel.to_markdown(),
+            "- **\\*notes\\* \\[draft\\]**\n- [https://www.example.com/a (b)](https://www.example.com/a%20%28b%29)\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hosts_xbel() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let bookmarks = xbel.new_bookmarks([
+            ("https://www.bank1.com/accounts", "Accounts"),
+            ("not an url", "Invalid"),
+            ("javascript:alert(1)", "Bookmarklet"),
+        ]);
+        if let Some((item_index, items)) = xbel.get_items_mut(&XbelPath::Id(2)) {
+            if let XbelItem::Folder(f) = &mut items[item_index] {
+                f.items.extend(bookmarks);
+            }
+        }
+
+        let hosts = xbel.hosts();
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts.get("www.bank1.com"), Some(&2));
+        assert_eq!(hosts.get("www.bank2.com"), Some(&1));
+        assert_eq!(hosts.get("www.bank3.com"), Some(&1));
+        assert!(Xbel::new(None).hosts().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parent_of_xbel() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert_eq!(xbel.parent_of(1), Parent::Root);
+        assert_eq!(xbel.parent_of(2).folder().map(|f| f.id.as_str()), Some("1"));
+        assert_eq!(xbel.parent_of(4).folder().map(|f| f.id.as_str()), Some("2"));
+        assert_eq!(xbel.parent_of(5).folder().map(|f| f.id.as_str()), Some("1"));
+        assert_eq!(xbel.parent_of(42), Parent::NotFound);
+        assert_eq!(xbel.parent_of(42).folder(), None);
+
+        if let Parent::Folder(f) = xbel.parent_of_mut(3) {
+            f.title = Title::new("my bank");
+        }
+        assert_eq!(xbel.get_folder(2).unwrap().title.text, "my bank");
+        assert_eq!(xbel.parent_of_mut(1), Parent::Root);
+        assert_eq!(xbel.parent_of_mut(42), Parent::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn count_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        assert_eq!(xbel.count(), (2, 3));
+        let xbel: Xbel = from_str(XBEL_EMPTY)?;
+        assert_eq!(xbel.count(), (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_titles_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let mut xbel: Xbel =
+            from_str(&XBEL_BANK.replace("<title>bank</title>", "<title>  my\n   bank </title>"))?;
+        let bookmark = xbel.new_bookmark("https://www.bank4.com", " Bank 4\t ");
+        xbel.items.push(bookmark);
+        assert_eq!(xbel.normalize_titles(), 2);
+        let titles = xbel
+            .into_iter()
+            .map(|i| i.get_title().text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(titles[1], "my bank");
+        assert_eq!(titles[5], "Bank 4");
+        assert_eq!(xbel.normalize_titles(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_mutable_iteration() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(&XBEL_BANK.replace("https://", "http://"))?;
+        for bookmark in xbel.bookmarks_mut() {
+            bookmark.href = bookmark.href.replacen("http://", "https://", 1);
+        }
+        assert_eq!(
+            xbel.bookmarks()
+                .map(|b| b.href.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://www.bank1.com/",
+                "https://www.bank2.com",
+                "https://www.bank3.com"
+            ]
+        );
+
+        // Visit order: a folder before its children
+        let mut ids = vec![];
+        xbel.for_each_mut(|item| {
+            ids.push(item.get_id().clone());
+            if let XbelItem::Folder(f) = item {
+                f.title.text = f.title.text.to_uppercase();
+            }
+        });
+        assert_eq!(ids, vec!["1", "2", "3", "4", "5"]);
+        assert_eq!(xbel.get_folder(2).unwrap().title.text, "BANK");
+
+        assert_eq!(Xbel::new(None).bookmarks_mut().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_walk() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let mut visited = vec![];
+        xbel.walk(|item, depth| visited.push((item.get_id().clone(), depth)));
+        let expected = [("1", 0), ("2", 1), ("3", 2), ("4", 2), ("5", 1)]
+            .map(|(id, depth)| (id.to_string(), depth))
+            .to_vec();
+        assert_eq!(visited, expected);
+
+        // Prefix titles with their depth
+        let mut visited = vec![];
+        xbel.walk_mut(|item, depth| {
+            visited.push((item.get_id().clone(), depth));
+            if let XbelItem::Folder(f) = item {
+                f.title.text = format!("{} {}", depth, f.title.text);
+            }
+        });
+        assert_eq!(visited, expected);
+        assert_eq!(xbel.get_folder(2).unwrap().title.text, "1 bank");
+        Ok(())
+    }
+
+    #[test]
+    fn xbel_rewrite_urls() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let rewritten = xbel.rewrite_urls(|href| Some(href.replace("bank1.com", "bank-one.com")));
+        assert_eq!(
+            rewritten,
+            vec![("3".to_string(), "https://www.bank1.com/".to_string())]
+        );
+        assert_eq!(
+            xbel.get_bookmark(3).unwrap().href,
+            "https://www.bank-one.com/"
+        );
+        // Unchanged: already rewritten or None
+        assert!(xbel
+            .rewrite_urls(|href| Some(href.replace("bank1.com", "bank-one.com")))
+            .is_empty());
+        assert!(xbel.rewrite_urls(|_| None).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn version_xbel() -> Result<(), XbelError> {
+        let mut xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        assert_eq!(xbel.version(), "1.0");
+        xbel.set_version("1.1");
+        let xbel = Xbel::from_xml_str(&xbel.try_to_string()?)?;
+        assert_eq!(xbel.version(), "1.1");
+        let xbel = Xbel::from_xml_str(&XBEL_BANK.replace(" version=\"1.0\"", ""))?;
+        assert_eq!(xbel.version(), Xbel::VERSION_DEFAULT);
+        assert!(xbel.try_to_string()?.contains("<xbel version=\"1.0\">"));
+        Ok(())
+    }
+
+    #[test]
+    fn get_bookmark_folder_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        assert_eq!(xbel.get_bookmark(4).unwrap().href, "https://www.bank2.com");
+        assert!(xbel.get_bookmark(2).is_none());
+        assert!(xbel.get_bookmark(42).is_none());
+        assert_eq!(xbel.get_folder(2).unwrap().title.text, "bank");
+        assert!(xbel.get_folder(4).is_none());
+        assert!(xbel.get_folder(42).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn tags_xbel() -> Result<(), XbelError> {
+        let content = XBEL_BANK.replace(r#"id="4">"#, r#"id="4" tags="bank, money,,">"#);
+        let xbel = Xbel::try_from_reader(content.as_bytes())?;
+        assert!(xbel.get_bookmark(3).unwrap().tags().is_empty());
+        let bookmark = xbel.get_bookmark(4).unwrap();
+        assert_eq!(bookmark.tags(), vec!["bank", "money"]);
+        assert!(bookmark.has_tag("Money"));
+        assert!(!bookmark.has_tag("mon"));
+
+        let mut bookmark = bookmark.clone();
+        bookmark.set_tags(&[" a", "", "b c "]);
+        assert_eq!(bookmark.tags.as_deref(), Some("a,b c"));
+        bookmark.set_tags::<&str>(&[]);
+        assert_eq!(bookmark.tags, None);
+
+        // Tags are written (as read)
+        let written = xbel.try_to_string()?;
+        assert!(written.contains(r#"id="4" tags="bank, money,,">"#));
+        assert_eq!(written.matches("tags=").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn added_xbel() -> Result<(), XbelError> {
+        let content = XBEL_BANK.replace(r#"id="4">"#, r#"id="4" added="2024-03-01T10:00:00">"#);
+        let xbel = Xbel::from_xml_str(&content)?;
+        assert_eq!(xbel.get_bookmark(3).unwrap().added, None);
+        assert_eq!(
+            xbel.get_bookmark(4).unwrap().added.as_deref(),
+            Some("2024-03-01T10:00:00")
+        );
+        let written = xbel.try_to_string()?;
+        assert!(written.contains(r#"id="4" added="2024-03-01T10:00:00">"#));
+        assert_eq!(written.matches("added=").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn flatten_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel =
+            from_str(&XBEL_BANK.replace("<title>bank</title>", "<title>my/bank</title>"))?;
+        let flat = xbel
+            .flatten()
+            .into_iter()
+            .map(|(path, b)| (path, b.id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flat,
+            vec![
+                ("admin/my\\/bank".to_string(), "3"),
+                ("admin/my\\/bank".to_string(), "4"),
+                ("admin".to_string(), "5"),
+            ]
+        );
+        let (item_index, items) = xbel.get_items(&XbelPath::Path(flat[0].0.clone())).unwrap();
+        assert_eq!(items[item_index].get_id(), "2");
+        Ok(())
+    }
+
+    #[test]
+    fn gzip_xbel() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let tmp_dir = tempfile::tempdir()?;
+
+        let path = tmp_dir.path().join("bookmarks.xbel.gz");
+        std::fs::File::create(&path)?;
+        xbel.try_to_file(&path)?;
+        assert!(std::fs::read(&path)?.starts_with(&GZIP_MAGIC));
+        assert_eq!(Xbel::try_from_file(&path)?, xbel);
+
+        // Plain Xbel file is unchanged
+        let path = tmp_dir.path().join("bookmarks.xbel");
+        std::fs::File::create(&path)?;
+        xbel.try_to_file(&path)?;
+        assert_eq!(std::fs::read_to_string(&path)?, xbel.try_to_string()?);
+        assert_eq!(Xbel::try_from_file(&path)?, xbel);
+        Ok(())
+    }
+
+    #[test]
+    fn try_to_file_replaces_atomically() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let tmp_dir = tempfile::tempdir()?;
+        let path = tmp_dir.path().join("bookmarks.xbel");
+        std::fs::write(&path, "previous content")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        }
+
+        xbel.try_to_file(&path)?;
+        assert_eq!(Xbel::try_from_file(&path)?, xbel);
+        // No temporary file left, permissions are kept
+        assert_eq!(std::fs::read_dir(tmp_dir.path())?.count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o644);
+        }
+
+        // A missing file is created
+        let new_path = tmp_dir.path().join("new.xbel.gz");
+        xbel.try_to_file(&new_path)?;
+        assert_eq!(Xbel::try_from_file(&new_path)?, xbel);
+        std::fs::remove_file(&new_path)?;
+
+        // On error, the file is left untouched
+        let missing_folder = tmp_dir.path().join("missing").join("bookmarks.xbel");
+        assert!(xbel.try_to_file(&missing_folder).is_err());
+        assert_eq!(Xbel::try_from_file(&path)?, xbel);
+        Ok(())
+    }
+
+    #[test]
+    fn subtree_xbel() -> Result<(), XbelError> {
+        let xbel = Xbel::from_xml_str(XBEL_BANK)?;
+        let subtree = xbel
+            .subtree(&XbelPath::Path("admin/bank".to_string()))
+            .unwrap();
+        assert_eq!(subtree.items.len(), 1);
+        assert_eq!(subtree.count(), (1, 2));
+        assert_eq!(subtree.stored_highest_id(), Some(4));
+        // Standalone Xbel
+        let written = subtree.try_to_string()?;
+        assert!(written.contains("highestId :4:"));
+        assert_eq!(Xbel::from_xml_str(&written)?.count(), (1, 2));
+
+        assert_eq!(xbel.subtree(&XbelPath::Id(5)).unwrap().count(), (0, 1));
+        assert_eq!(xbel.subtree(&XbelPath::Root).unwrap().count(), (2, 3));
+        assert!(xbel.subtree(&XbelPath::Id(42)).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn find_path_of_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        let titles = |id| {
+            xbel.find_path_of(id)
+                .map(|p| p.iter().map(|f| f.title.text.as_str()).collect::<Vec<_>>())
+        };
+        assert_eq!(titles(3), Some(vec!["admin", "bank"]));
+        assert_eq!(titles(5), Some(vec!["admin"]));
+        assert_eq!(titles(1), Some(vec![]));
+        assert_eq!(titles(42), None);
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn get_items_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
+        let xbel: Xbel = from_str(XBEL_BANK)?;
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(4)).unwrap();
+        assert_eq!(item_index, 1);
+        assert_eq!(items[item_index].get_id(), "4");
+        let (item_index, items) = xbel
+            .get_items(&XbelPath::Path("admin/bank".to_string()))
+            .unwrap();
+        assert_eq!(items[item_index].get_id(), "2");
+        assert!(xbel.get_items(&XbelPath::Id(42)).is_none());
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn merge_xbel() -> Result<(), XbelError> {
+        let mut xbel = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let other = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let mapping = xbel.merge(other, false);
+
+        assert_eq!(xbel.items.len(), 2);
+        assert_eq!(xbel.get_highest_id(), 10);
+        assert_eq!(mapping.len(), 5);
+        assert_eq!(mapping.get("1"), Some(&"6".to_string()));
+        assert_eq!(mapping.get("5"), Some(&"10".to_string()));
+        // All ids are unique
+        let mut ids = xbel.into_iter().map(|i| i.get_id()).collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    #[traced_test]
+    fn merge_xbel_fuse() -> Result<(), XbelError> {
+        let mut xbel = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let other = Xbel::new(Some(vec![XbelItem::Folder(Folder::new(
+            "1",
+            "admin",
+            Some(vec![
+                XbelItem::Folder(Folder::new(
+                    "2",
+                    "bank",
+                    Some(vec![XbelItem::new_bookmark(
+                        "3",
+                        "https://www.bank4.com",
+                        "Bank 4",
+                    )]),
+                )),
+                XbelItem::Folder(Folder::new("4", "insurance", None)),
+            ]),
+        ))]));
+        let mapping = xbel.merge(other, true);
+
+        assert_eq!(xbel.items.len(), 1);
+        assert_eq!(mapping.get("1"), Some(&"1".to_string()));
+        assert_eq!(mapping.get("2"), Some(&"2".to_string()));
+        assert_eq!(mapping.get("3"), Some(&"6".to_string()));
+        assert_eq!(mapping.get("4"), Some(&"7".to_string()));
+        let (item_index, items) = xbel.get_items(&XbelPath::Id(6)).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[item_index].get_url().unwrap(),
+            "https://www.bank4.com"
+        );
+        Ok(())
+    }
+
     #[test]
     #[traced_test]
     fn write_xbel() -> Result<(), quick_xml::errors::serialize::DeError> {
@@ -749,6 +3541,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[traced_test]
+    fn xbel_json_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        // Xbel -> Json -> Xbel
+        let bank_v1 = std::fs::read_to_string("../ressources/bookmarks_bank_v1.xbel")?;
+        let xbel = Xbel::try_from_reader(bank_v1.as_bytes())?;
+        let json = serde_json::to_string(&xbel)?;
+        let xbel_from_json: Xbel = serde_json::from_str(json.as_str())?;
+
+        assert_eq!(xbel_from_json.items, xbel.items);
+        assert_eq!(xbel_from_json.to_string(), bank_v1);
+        Ok(())
+    }
+
     /*
     #[test]
     fn write_xbel_ser() -> Result<(), quick_xml::errors::serialize::DeError> {
@@ -0,0 +1,309 @@
+// std
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+// third-party
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+// internal
+use crate::xbel_format::{Bookmark, Folder, Title, XbelError, XbelItem};
+
+/// An event read by `XbelReader`: either a `XbelItem` or the end of a Folder
+///
+/// Folders are yielded without their children (`items` is empty), the children follow as
+/// separate events until `End` (as in `XbelItemOrEnd`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum XbelReadEvent {
+    Item(XbelItem),
+    End(String), // folder id
+}
+
+/// A streaming (pull) reader for Xbel files
+///
+/// Unlike `Xbel::try_from_reader`, the whole tree is never built in memory: folders & bookmarks
+/// are yielded one by one (DFS order), suitable for read only tools (find, count...) on very
+/// large files. Use `Xbel` to edit bookmarks.
+pub struct XbelReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    /// Events ready to be yielded
+    events: VecDeque<XbelReadEvent>,
+    /// Folder (start tag read) waiting for its title
+    folder: Option<Folder>,
+    /// Bookmark (start tag read) waiting for its end tag
+    bookmark: Option<Bookmark>,
+    /// Ids of the folders currently open
+    folder_ids: Vec<String>,
+    /// Title text being read (if inside a folder or bookmark title)
+    title: Option<String>,
+    done: bool,
+}
+
+impl XbelReader<BufReader<File>> {
+    /// Stream a Xbel file
+    pub fn from_file<T: AsRef<Path>>(path: T) -> Result<Self, XbelError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> XbelReader<R> {
+    pub fn new(reader: R) -> Self {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+        Self {
+            reader,
+            buf: Vec::new(),
+            events: VecDeque::new(),
+            folder: None,
+            bookmark: None,
+            folder_ids: Vec::new(),
+            title: None,
+            done: false,
+        }
+    }
+
+    /// Yield the folder waiting for its title (folder without a title element)
+    fn flush_folder(&mut self) {
+        if let Some(folder) = self.folder.take() {
+            self.events
+                .push_back(XbelReadEvent::Item(XbelItem::Folder(folder)));
+        }
+    }
+
+    fn start_folder(&mut self, e: &BytesStart) -> Result<(), XbelError> {
+        self.flush_folder();
+        let id = attribute(e, "id")?.unwrap_or_default();
+        self.folder_ids.push(id.clone());
+        self.folder = Some(Folder::new(id.as_str(), "", None));
+        Ok(())
+    }
+
+    fn start_bookmark(&mut self, e: &BytesStart) -> Result<(), XbelError> {
+        self.flush_folder();
+        let mut bookmark = Bookmark::new(
+            attribute(e, "id")?.unwrap_or_default().as_str(),
+            attribute(e, "href")?.unwrap_or_default().as_str(),
+            "",
+        );
+        bookmark.added = attribute(e, "added")?;
+        bookmark.tags = attribute(e, "tags")?;
+        self.bookmark = Some(bookmark);
+        Ok(())
+    }
+
+    fn alias(&mut self, e: &BytesStart) -> Result<(), XbelError> {
+        self.flush_folder();
+        let reference = attribute(e, "ref")?.unwrap_or_default();
+        self.events
+            .push_back(XbelReadEvent::Item(XbelItem::Alias { reference }));
+        Ok(())
+    }
+
+    fn end_folder(&mut self) {
+        self.flush_folder();
+        if let Some(id) = self.folder_ids.pop() {
+            self.events.push_back(XbelReadEvent::End(id));
+        }
+    }
+
+    fn end_bookmark(&mut self) {
+        if let Some(bookmark) = self.bookmark.take() {
+            self.events
+                .push_back(XbelReadEvent::Item(XbelItem::Bookmark(bookmark)));
+        }
+    }
+
+    fn end_title(&mut self) {
+        let title = Title {
+            text: self.title.take().unwrap_or_default(),
+        };
+        if let Some(bookmark) = self.bookmark.as_mut() {
+            bookmark.title = title;
+        } else if let Some(mut folder) = self.folder.take() {
+            folder.title = title;
+            self.events
+                .push_back(XbelReadEvent::Item(XbelItem::Folder(folder)));
+        }
+    }
+
+    /// Read xml events until at least one `XbelReadEvent` is available (or end of file)
+    fn read_events(&mut self) -> Result<(), XbelError> {
+        // Note: buffer is taken out of self as read events borrow it
+        let mut buf = std::mem::take(&mut self.buf);
+        let res = self.read_events_into(&mut buf);
+        self.buf = buf;
+        res
+    }
+
+    fn read_events_into(&mut self, buf: &mut Vec<u8>) -> Result<(), XbelError> {
+        while self.events.is_empty() {
+            buf.clear();
+            // Note: match the local name (a namespace prefix, e.g. <x:folder>, is ignored)
+            match self.reader.read_event_into(buf)? {
+                Event::Start(e) => match e.local_name().as_ref() {
+                    b"folder" => self.start_folder(&e)?,
+                    b"bookmark" => self.start_bookmark(&e)?,
+                    b"alias" => self.alias(&e)?,
+                    b"title" if self.folder.is_some() || self.bookmark.is_some() => {
+                        self.title = Some(String::new())
+                    }
+                    _ => {}
+                },
+                Event::Empty(e) => match e.local_name().as_ref() {
+                    b"folder" => {
+                        self.start_folder(&e)?;
+                        self.end_folder();
+                    }
+                    b"bookmark" => {
+                        self.start_bookmark(&e)?;
+                        self.end_bookmark();
+                    }
+                    b"alias" => self.alias(&e)?,
+                    _ => {}
+                },
+                Event::Text(t) => {
+                    if let Some(title) = self.title.as_mut() {
+                        title.push_str(&t.unescape()?);
+                    }
+                }
+                Event::CData(t) => {
+                    if let Some(title) = self.title.as_mut() {
+                        title.push_str(std::str::from_utf8(&t)?);
+                    }
+                }
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"folder" => self.end_folder(),
+                    b"bookmark" => self.end_bookmark(),
+                    b"title" if self.title.is_some() => self.end_title(),
+                    _ => {}
+                },
+                Event::Eof => {
+                    self.flush_folder();
+                    self.done = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for XbelReader<R> {
+    type Item = Result<XbelReadEvent, XbelError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_empty() && !self.done {
+            if let Err(e) = self.read_events() {
+                // Stop on first error
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+/// Unescaped attribute value (if any)
+fn attribute(e: &BytesStart, name: &str) -> Result<Option<String>, XbelError> {
+    let attr = e.try_get_attribute(name).map_err(quick_xml::Error::from)?;
+    Ok(attr
+        .map(|a| a.unescape_value().map(|v| v.into_owned()))
+        .transpose()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbel_format::{Xbel, XbelItemOrEnd, XbelNestingIterator};
+
+    const XBEL_BANK: &str = r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+            <xbel version="1.0">
+            <!--- highestId :6: for Floccus bookmark sync browser extension -->
+
+            <folder id="1">
+                <title>admin</title>
+                <folder id="2">
+                    <title>bank</title>
+                    <bookmark href="https://www.bank1.com/" id="3">
+                        <title>Bank 1 - Best bank in the world</title>
+                    </bookmark>
+                    <bookmark href="https://www.bank2.com" id="4" added="2024-03-01T10:00:00" tags="bank">
+                        <title>Bank 2 because 2 &gt; 1 !#€</title>
+                    </bookmark>
+                </folder>
+                <bookmark href="https://www.bank3.com" id="5">
+                    <title>My current bank U+1F929 </title>
+                </bookmark>
+                <folder id="6"/>
+            </folder>
+            </xbel>
+        "#;
+
+    #[test]
+    fn read_xbel_streaming() -> Result<(), XbelError> {
+        let events =
+            XbelReader::new(XBEL_BANK.trim_start().as_bytes()).collect::<Result<Vec<_>, _>>()?;
+
+        // Same events as the in memory Xbel (folders without their children)
+        let xbel = Xbel::try_from_reader(XBEL_BANK.as_bytes())?;
+        let expected = XbelNestingIterator::new(&xbel)
+            .map(|item| match item {
+                XbelItemOrEnd::Item(XbelItem::Folder(f)) => {
+                    XbelReadEvent::Item(XbelItem::Folder(Folder::new(&f.id, &f.title.text, None)))
+                }
+                XbelItemOrEnd::Item(item) => XbelReadEvent::Item(item.clone()),
+                XbelItemOrEnd::End { id, .. } => XbelReadEvent::End(id),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected);
+        assert_eq!(events.len(), 9);
+        assert_eq!(events.last(), Some(&XbelReadEvent::End("1".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_streaming_namespaced() -> Result<(), XbelError> {
+        let content = XBEL_BANK
+            .trim_start()
+            .replace(
+                "<xbel ",
+                "<XBEL xmlns:x=\"http://www.python.org/topics/xml/xbel/\" ",
+            )
+            .replace("</xbel>", "</XBEL>")
+            .replace("<folder", "<x:folder")
+            .replace("</folder>", "</x:folder>");
+        let events = XbelReader::new(content.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+        let expected =
+            XbelReader::new(XBEL_BANK.trim_start().as_bytes()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(events, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_streaming_alias() -> Result<(), XbelError> {
+        let content = XBEL_BANK.trim_start().replace(
+            "</xbel>",
+            "<alias ref=\"2\"/><alias ref=\"3\"></alias></xbel>",
+        );
+        let events = XbelReader::new(content.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+        let alias = |reference: &str| {
+            XbelReadEvent::Item(XbelItem::Alias {
+                reference: reference.to_string(),
+            })
+        };
+        assert_eq!(events[events.len() - 2..], [alias("2"), alias("3")]);
+        Ok(())
+    }
+
+    #[test]
+    fn read_xbel_streaming_error() {
+        let content = XBEL_BANK.trim_start().replace("</folder>", "</foo>");
+        let mut reader = XbelReader::new(content.as_bytes());
+        assert!(reader.any(|event| event.is_err()));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,191 @@
+// std
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+// internal
+use crate::xbel_format::{Xbel, XbelItem};
+
+/// A problem found in a `Xbel` (see `Xbel::validate`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// Id used by more than one item
+    DuplicateId(String),
+    /// Id is not an integer (as expected by Floccus)
+    NonNumericId(String),
+    /// Item without id (ids are assigned when writing, see `Xbel::assign_missing_ids`)
+    MissingId { title: String },
+    /// Bookmark without url
+    EmptyHref { id: String },
+    /// Folder without title
+    EmptyFolderTitle { id: String },
+    /// Alias referencing an id not found (or not an integer)
+    DanglingAlias { reference: String },
+    /// Stored highestId is lower than the highest id found (a higher stored highestId is expected
+    /// once the items with the highest ids are removed)
+    StaleHighestId { stored: u64, highest: u64 },
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::DuplicateId(id) => write!(f, "Duplicate id: {}", id),
+            ValidationIssue::NonNumericId(id) => write!(f, "Id is not an integer: {:?}", id),
+            ValidationIssue::MissingId { title } => write!(f, "Item {:?} has no id", title),
+            ValidationIssue::EmptyHref { id } => write!(f, "Bookmark (id: {}) has no url", id),
+            ValidationIssue::EmptyFolderTitle { id } => {
+                write!(f, "Folder (id: {}) has no title", id)
+            }
+            ValidationIssue::DanglingAlias { reference } => {
+                write!(f, "Alias references an unknown id: {:?}", reference)
+            }
+            ValidationIssue::StaleHighestId { stored, highest } => write!(
+                f,
+                "Stored highestId ({}) is lower than the highest id found ({})",
+                stored, highest
+            ),
+        }
+    }
+}
+
+impl Xbel {
+    /// Check for common problems (duplicate, missing or non integer ids, empty urls or folder
+    /// titles, aliases referencing an unknown id, stale highestId)
+    ///
+    /// Returns all issues found (empty if the Xbel is valid)
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let mut ids = HashSet::new();
+        let mut duplicate_ids = HashSet::new();
+        let mut highest_id = 0;
+
+        for item in self {
+            // Note: an alias has no id
+            if let XbelItem::Alias { reference } = item {
+                if self.resolve_alias(item).is_none() {
+                    issues.push(ValidationIssue::DanglingAlias {
+                        reference: reference.clone(),
+                    });
+                }
+                continue;
+            }
+            let id = item.get_id();
+            if item.has_missing_id() {
+                issues.push(ValidationIssue::MissingId {
+                    title: item.get_title().text.clone(),
+                });
+            } else {
+                if !ids.insert(id) && duplicate_ids.insert(id) {
+                    issues.push(ValidationIssue::DuplicateId(id.clone()));
+                }
+                match id.parse::<u64>() {
+                    Ok(id) => highest_id = highest_id.max(id),
+                    Err(_) => issues.push(ValidationIssue::NonNumericId(id.clone())),
+                }
+            }
+            match item {
+                XbelItem::Bookmark(b) if b.href.trim().is_empty() => {
+                    issues.push(ValidationIssue::EmptyHref { id: id.clone() })
+                }
+                XbelItem::Folder(f) if f.title.text.trim().is_empty() => {
+                    issues.push(ValidationIssue::EmptyFolderTitle { id: id.clone() })
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(stored) = self.stored_highest_id() {
+            if stored < highest_id {
+                issues.push(ValidationIssue::StaleHighestId {
+                    stored,
+                    highest: highest_id,
+                });
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XBEL_ISSUES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE xbel PUBLIC "+//IDN python.org//DTD XML Bookmark Exchange Language 1.0//EN//XML" "http://pyxml.sourceforge.net/topics/dtds/xbel.dtd">
+<xbel version="1.0">
+<!--- highestId :1: for Floccus bookmark sync browser extension -->
+<folder id="1">
+    <title></title>
+    <bookmark href="https://www.bank1.com/" id="2">
+        <title>Bank 1</title>
+    </bookmark>
+    <bookmark href="" id="2">
+        <title>Bank 2</title>
+    </bookmark>
+    <bookmark href="https://www.bank3.com/" id="b4">
+        <title>Bank 3</title>
+    </bookmark>
+</folder>
+</xbel>
+"#;
+
+    #[test]
+    fn validate_xbel() -> Result<(), crate::XbelError> {
+        let xbel = Xbel::try_from_reader(XBEL_ISSUES.as_bytes())?;
+        assert_eq!(
+            xbel.validate(),
+            vec![
+                ValidationIssue::EmptyFolderTitle {
+                    id: "1".to_string()
+                },
+                ValidationIssue::DuplicateId("2".to_string()),
+                ValidationIssue::EmptyHref {
+                    id: "2".to_string()
+                },
+                ValidationIssue::NonNumericId("b4".to_string()),
+                ValidationIssue::StaleHighestId {
+                    stored: 1,
+                    highest: 2
+                },
+            ]
+        );
+
+        let xbel = Xbel::new(Some(vec![XbelItem::new_bookmark("1", "https://a.b", "A")]));
+        assert!(xbel.validate().is_empty());
+        // Stored highestId can be higher (e.g. after removing the item with the highest id)
+        let xbel = Xbel::try_from_reader(XBEL_ISSUES.replace(":1:", ":9:").as_bytes())?;
+        assert!(!xbel
+            .validate()
+            .iter()
+            .any(|issue| matches!(issue, ValidationIssue::StaleHighestId { .. })));
+        // Missing ids (not reported as duplicate or non integer ids)
+        let xbel = Xbel::try_from_file("../ressources/bookmarks_no_id.xbel")?;
+        assert_eq!(
+            xbel.validate(),
+            vec![
+                ValidationIssue::MissingId {
+                    title: "admin".to_string()
+                },
+                ValidationIssue::MissingId {
+                    title: "Bank 2 because 2 gt 1 !#€".to_string()
+                },
+            ]
+        );
+        // Aliases (no id): only the referenced id is checked
+        let xbel = Xbel::new(Some(vec![
+            XbelItem::new_bookmark("1", "https://a.b", "A"),
+            XbelItem::Alias {
+                reference: "1".to_string(),
+            },
+            XbelItem::Alias {
+                reference: "7".to_string(),
+            },
+        ]));
+        assert_eq!(
+            xbel.validate(),
+            vec![ValidationIssue::DanglingAlias {
+                reference: "7".to_string()
+            }]
+        );
+        Ok(())
+    }
+}